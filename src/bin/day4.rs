@@ -0,0 +1,34 @@
+use std::{env, fs, process};
+
+use advent_of_code::day4::{assignments, generate, parse_queries, IntervalTree, Query};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() != 3 {
+        eprintln!("usage: {} <assignments file> <queries file>", args[0]);
+        process::exit(1);
+    }
+    let read = |path: &str| {
+        fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        })
+    };
+    let tree = IntervalTree::new(&assignments(&generate(&read(&args[1]))));
+    let queries = parse_queries(&read(&args[2])).unwrap_or_else(|e| {
+        eprintln!("{}: {}", args[2], e);
+        process::exit(1);
+    });
+    for query in queries {
+        let label = match query {
+            Query::Contains(section) => section.to_string(),
+            Query::Intersects(range) => format!("{}-{}", range.start(), range.end()),
+        };
+        let elves = tree
+            .answer(&query)
+            .iter()
+            .map(|idx| format!(" {}", idx))
+            .collect::<String>();
+        println!("{}:{}", label, elves);
+    }
+}
@@ -0,0 +1,29 @@
+use std::io::{self, BufReader, Read};
+
+use advent_of_code::day6::Detector;
+
+fn main() -> io::Result<()> {
+    let mut packet = Detector::new(4);
+    let mut message = Detector::new(14);
+    let mut push = |b| {
+        if let Some(position) = packet.push(b) {
+            println!("start-of-packet {}", position);
+        }
+        if let Some(position) = message.push(b) {
+            println!("start-of-message {}", position);
+        }
+    };
+    // Line endings are only part of the stream if something follows them,
+    // so that positions stay byte offsets but a final newline is ignored.
+    let mut line_ending = Vec::new();
+    for b in BufReader::new(io::stdin().lock()).bytes() {
+        let b = b?;
+        if b == b'\n' || b == b'\r' {
+            line_ending.push(b);
+            continue;
+        }
+        line_ending.drain(..).for_each(&mut push);
+        push(b);
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::{env, fs, path::Path, process};
+
+use advent_of_code::day7::{
+    du, generate, plan_cleanup, render_tree, small_directories_total, smallest_cleanup, to_json,
+    to_transcript, DiskPolicy, Vfs,
+};
+
+const USAGE: &str = "usage: day7 <solve|plan|tree|du|json|export> [--capacity N] [--required N] \
+[--threshold N] [--all] <transcript>
+       day7 import <directory>";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| fail(USAGE));
+    let mut policy = DiskPolicy::default();
+    let mut all = false;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_else(|| fail(&format!("{} needs a number", arg)))
+        };
+        match arg.as_str() {
+            "--capacity" => policy.capacity = value(),
+            "--required" => policy.required_free = value(),
+            "--threshold" => policy.small_threshold = value(),
+            "--all" => all = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let path = path.unwrap_or_else(|| fail(USAGE));
+    if command == "import" {
+        let vfs =
+            Vfs::from_dir(Path::new(&path)).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+        println!("{}", to_transcript(&vfs));
+        return;
+    }
+    let transcript =
+        fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    let input = generate(&transcript).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+    let vfs = || Vfs::from_transcript(&input).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)));
+
+    match command.as_str() {
+        "solve" => {
+            let vfs = vfs();
+            println!(
+                "small directories: {}",
+                small_directories_total(&vfs, &policy)
+            );
+            match smallest_cleanup(&vfs, &policy) {
+                Some(size) => println!("smallest cleanup: {}", size),
+                None => println!("smallest cleanup: none"),
+            }
+        }
+        "plan" => match plan_cleanup(&vfs(), &policy) {
+            Some(cleanup) => {
+                let kind = if cleanup.exact {
+                    "exact"
+                } else {
+                    "approximate"
+                };
+                println!("freed {} ({})", cleanup.freed, kind);
+                for path in cleanup.paths {
+                    println!("{}", path);
+                }
+            }
+            None => fail("not enough space even after deleting everything"),
+        },
+        "tree" => println!("{}", render_tree(&vfs())),
+        "du" => {
+            for (size, path) in du(&vfs(), all) {
+                println!("{}\t{}", size, path);
+            }
+        }
+        "json" => println!("{}", to_json(&vfs())),
+        "export" => {
+            let dir = env::temp_dir().join(format!("day7-{}", process::id()));
+            vfs()
+                .write_to(&dir)
+                .unwrap_or_else(|e| fail(&format!("{}: {}", dir.display(), e)));
+            println!("{}", dir.display());
+        }
+        _ => fail(USAGE),
+    }
+}
@@ -6,7 +6,7 @@ pub enum Instruction {
     Noop,
 }
 
-struct CPU {
+struct Cpu {
     tick: u32,
     x: i32,
     program: Vec<Instruction>,
@@ -28,7 +28,7 @@ impl ExecutingInstruction {
     }
 }
 
-impl CPU {
+impl Cpu {
     fn new(program: &[Instruction]) -> Self {
         Self {
             tick: 1,
@@ -47,7 +47,7 @@ impl CPU {
     }
 
     fn end_cycle(&mut self) {
-        let mut currently_executing = self.currently_executing.as_mut().unwrap();
+        let currently_executing = self.currently_executing.as_mut().unwrap();
         currently_executing.cycles += 1;
         match currently_executing.instruction {
             Instruction::Addx(value) => {
@@ -94,7 +94,7 @@ pub fn generate(input: &str) -> Vec<Instruction> {
 
 #[aoc(day10, part1)]
 pub fn solve_part1(input: &[Instruction]) -> i32 {
-    let mut cpu = CPU::new(input);
+    let mut cpu = Cpu::new(input);
 
     let mut ret = 0;
 
@@ -121,7 +121,7 @@ pub fn solve_part1(input: &[Instruction]) -> i32 {
 
 #[aoc(day10, part2)]
 pub fn solve_part2(input: &[Instruction]) -> String {
-    let mut cpu = CPU::new(input);
+    let mut cpu = Cpu::new(input);
 
     let mut out = String::new();
 
@@ -313,7 +313,7 @@ noop";
 
     #[test]
     fn test3() {
-        let mut cpu = CPU::new(&generate(EXAMPLE1));
+        let mut cpu = Cpu::new(&generate(EXAMPLE1));
 
         cpu.begin_cycle();
         assert_eq!(cpu.tick, 1);
@@ -361,7 +361,7 @@ noop";
 
     #[test]
     fn test5() {
-        let mut cpu = CPU::new(&generate(EXAMPLE2));
+        let mut cpu = Cpu::new(&generate(EXAMPLE2));
 
         cpu.begin_cycle();
         assert_eq!(cpu.pixel(), '#');
@@ -38,7 +38,7 @@ impl Monkey {
         on_false: usize,
     ) -> Self {
         Self {
-            items: VecDeque::from_iter(items.iter().map(|i| *i)),
+            items: VecDeque::from_iter(items.iter().copied()),
             operation,
             divisor,
             on_true,
@@ -65,8 +65,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
                 .next()
                 .unwrap()
                 .split('=')
-                .skip(1)
-                .next()
+                .nth(1)
                 .unwrap()
                 .trim_start();
             let op = if op == "old * old" {
@@ -75,8 +74,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
                 let term = op
                     .trim_start()
                     .split(' ')
-                    .skip(2)
-                    .next()
+                    .nth(2)
                     .unwrap()
                     .parse::<u64>()
                     .unwrap();
@@ -91,8 +89,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
                 .unwrap()
                 .trim_start()
                 .split(' ')
-                .skip(3)
-                .next()
+                .nth(3)
                 .unwrap()
                 .parse::<u64>()
                 .unwrap();
@@ -101,8 +98,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
                 .unwrap()
                 .trim_start()
                 .split(' ')
-                .skip(5)
-                .next()
+                .nth(5)
                 .unwrap()
                 .parse()
                 .unwrap();
@@ -111,8 +107,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
                 .unwrap()
                 .trim_start()
                 .split(' ')
-                .skip(5)
-                .next()
+                .nth(5)
                 .unwrap()
                 .parse()
                 .unwrap();
@@ -124,7 +119,7 @@ pub fn generate(input: &str) -> Vec<Monkey> {
 fn run_round(monkeys: &mut [Monkey], with_div: bool) -> Vec<u64> {
     let mut inspects = vec![0; monkeys.len()];
     let common = monkeys.iter().map(|m| m.divisor).product::<u64>();
-    for idx in 0..monkeys.len() {
+    for (idx, inspect) in inspects.iter_mut().enumerate() {
         let monkey = monkeys.get_mut(idx).unwrap();
         let mut to_send = Vec::<(usize, u64)>::default();
         loop {
@@ -132,7 +127,7 @@ fn run_round(monkeys: &mut [Monkey], with_div: bool) -> Vec<u64> {
             if item.is_none() {
                 break;
             }
-            inspects[idx] = inspects[idx] + 1;
+            *inspect += 1;
             let item = monkey.operation.execute(item.unwrap());
             let item = if with_div { item / 3 } else { item % common };
             let test_succeeded = item % monkey.divisor == 0;
@@ -158,7 +153,7 @@ pub fn solve_part1(input: &[Monkey]) -> u64 {
     for _ in 0..20 {
         let round_inspects = run_round(&mut monkeys, true);
         for idx in 0..inspects.len() {
-            inspects[idx] = inspects[idx] + round_inspects[idx];
+            inspects[idx] += round_inspects[idx];
         }
     }
 
@@ -170,10 +165,10 @@ pub fn solve_part2(input: &[Monkey]) -> u64 {
     let mut monkeys = input.to_vec();
     let mut inspects = vec![0; monkeys.len()];
 
-    for round in 0..10000 {
+    for _ in 0..10000 {
         let round_inspects = run_round(&mut monkeys, false);
         for idx in 0..inspects.len() {
-            inspects[idx] = inspects[idx] + round_inspects[idx];
+            inspects[idx] += round_inspects[idx];
         }
     }
 
@@ -1,6 +1,6 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-pub enum RPS {
+pub enum Rps {
     Rock,
     Paper,
     Scissors,
@@ -12,34 +12,34 @@ pub enum Outcome {
     Draw,
 }
 
-impl RPS {
+impl Rps {
     fn as_outcome(&self) -> Outcome {
         match self {
-            RPS::Rock => Outcome::Loss,
-            RPS::Paper => Outcome::Draw,
-            RPS::Scissors => Outcome::Win,
+            Rps::Rock => Outcome::Loss,
+            Rps::Paper => Outcome::Draw,
+            Rps::Scissors => Outcome::Win,
         }
     }
 }
 
 pub struct RPSRound {
-    yours: RPS,
-    theirs: RPS,
+    yours: Rps,
+    theirs: Rps,
 }
 
 impl RPSRound {
     fn new(theirs: char, yours: char) -> Self {
         Self {
             yours: match yours {
-                'X' => RPS::Rock,
-                'Y' => RPS::Paper,
-                'Z' => RPS::Scissors,
+                'X' => Rps::Rock,
+                'Y' => Rps::Paper,
+                'Z' => Rps::Scissors,
                 _ => panic!(),
             },
             theirs: match theirs {
-                'A' => RPS::Rock,
-                'B' => RPS::Paper,
-                'C' => RPS::Scissors,
+                'A' => Rps::Rock,
+                'B' => Rps::Paper,
+                'C' => Rps::Scissors,
                 _ => panic!(),
             },
         }
@@ -48,14 +48,14 @@ impl RPSRound {
     fn value(&self) -> u32 {
         let mut out = 0;
         out += match self.yours {
-            RPS::Rock => 1,
-            RPS::Paper => 2,
-            RPS::Scissors => 3,
+            Rps::Rock => 1,
+            Rps::Paper => 2,
+            Rps::Scissors => 3,
         };
         out += match (&self.yours, &self.theirs) {
-            (RPS::Rock, RPS::Paper) | (RPS::Paper, RPS::Scissors) | (RPS::Scissors, RPS::Rock) => 0,
-            (RPS::Rock, RPS::Rock) | (RPS::Paper, RPS::Paper) | (RPS::Scissors, RPS::Scissors) => 3,
-            (RPS::Rock, RPS::Scissors) | (RPS::Paper, RPS::Rock) | (RPS::Scissors, RPS::Paper) => 6,
+            (Rps::Rock, Rps::Paper) | (Rps::Paper, Rps::Scissors) | (Rps::Scissors, Rps::Rock) => 0,
+            (Rps::Rock, Rps::Rock) | (Rps::Paper, Rps::Paper) | (Rps::Scissors, Rps::Scissors) => 3,
+            (Rps::Rock, Rps::Scissors) | (Rps::Paper, Rps::Rock) | (Rps::Scissors, Rps::Paper) => 6,
         };
         out
     }
@@ -65,15 +65,15 @@ impl RPSRound {
         let outcome = self.yours.as_outcome();
 
         out += match (&self.theirs, &outcome) {
-            (RPS::Rock, Outcome::Draw)
-            | (RPS::Paper, Outcome::Loss)
-            | (RPS::Scissors, Outcome::Win) => 1,
-            (RPS::Rock, Outcome::Win)
-            | (RPS::Paper, Outcome::Draw)
-            | (RPS::Scissors, Outcome::Loss) => 2,
-            (RPS::Rock, Outcome::Loss)
-            | (RPS::Paper, Outcome::Win)
-            | (RPS::Scissors, Outcome::Draw) => 3,
+            (Rps::Rock, Outcome::Draw)
+            | (Rps::Paper, Outcome::Loss)
+            | (Rps::Scissors, Outcome::Win) => 1,
+            (Rps::Rock, Outcome::Win)
+            | (Rps::Paper, Outcome::Draw)
+            | (Rps::Scissors, Outcome::Loss) => 2,
+            (Rps::Rock, Outcome::Loss)
+            | (Rps::Paper, Outcome::Win)
+            | (Rps::Scissors, Outcome::Draw) => 3,
         };
 
         out += match outcome {
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, error::Error, fmt};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
@@ -16,33 +16,363 @@ impl Backpack {
             compartment_two: compartment_two.to_string(),
         }
     }
+
+    fn items(&self) -> ItemSet {
+        ItemSet::from(self.compartment_one.as_str())
+            .union(&ItemSet::from(self.compartment_two.as_str()))
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ParseError {
+    OddLength {
+        line: usize,
+        length: usize,
+    },
+    InvalidItem {
+        line: usize,
+        column: usize,
+        item: char,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::OddLength { line, length } => write!(
+                f,
+                "line {}: {} items cannot be split into two compartments",
+                line, length
+            ),
+            ParseError::InvalidItem { line, column, item } => {
+                write!(
+                    f,
+                    "line {}, column {}: invalid item {:?}",
+                    line, column, item
+                )
+            }
+        }
+    }
 }
 
+impl Error for ParseError {}
+
 #[aoc_generator(day3)]
-pub fn generate(input: &str) -> Vec<Backpack> {
+pub fn generate(input: &str) -> Result<Vec<Backpack>, ParseError> {
     input
         .lines()
-        .map(|l| {
+        .enumerate()
+        .map(|(idx, l)| {
+            let line = idx + 1;
+            if let Some((column, item)) = l
+                .chars()
+                .enumerate()
+                .find(|(_, c)| try_priority(*c).is_none())
+            {
+                return Err(ParseError::InvalidItem {
+                    line,
+                    column: column + 1,
+                    item,
+                });
+            }
+            if l.len() % 2 != 0 {
+                return Err(ParseError::OddLength {
+                    line,
+                    length: l.len(),
+                });
+            }
             let pivot = l.len() / 2;
             let first = &l[..pivot];
             let second = &l[pivot..];
-            Backpack::new(first, second)
+            Ok(Backpack::new(first, second))
         })
         .collect()
 }
 
+fn try_priority(c: char) -> Option<u32> {
+    match c {
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
+}
+
 fn to_priority(c: char) -> u32 {
-    let ascii: u32 = c.into();
-    let ascii = ascii - 64;
-    if ascii > 32 {
-        ascii - 32
+    try_priority(c).unwrap_or_else(|| panic!("invalid item {:?}", c))
+}
+
+fn from_priority(priority: u32) -> char {
+    if priority > 26 {
+        char::from_u32(priority - 27 + 'A' as u32).unwrap()
     } else {
-        ascii + 26
+        char::from_u32(priority - 1 + 'a' as u32).unwrap()
+    }
+}
+
+/// A set of item types, stored as one bit per priority (1 through 52).
+#[derive(PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.0 |= 1 << to_priority(c);
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.0 & (1 << to_priority(c)) != 0
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn priorities(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (1..=52).filter(move |p| bits & (1 << p) != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = char> {
+        self.priorities().map(from_priority)
+    }
+}
+
+impl From<&str> for ItemSet {
+    fn from(s: &str) -> Self {
+        s.chars().collect()
+    }
+}
+
+impl FromIterator<char> for ItemSet {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut out = Self::new();
+        for c in iter {
+            out.insert(c);
+        }
+        out
     }
 }
 
+/// Exchanges the item at `one` in the first compartment with the item at
+/// `two` in the second.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Swap {
+    pub one: usize,
+    pub two: usize,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Reorganization {
+    pub misplaced: ItemSet,
+    pub swaps: Vec<Swap>,
+}
+
+impl Backpack {
+    pub fn misplaced(&self) -> ItemSet {
+        ItemSet::from(self.compartment_one.as_str())
+            .intersection(&ItemSet::from(self.compartment_two.as_str()))
+    }
+
+    pub fn apply(&mut self, swaps: &[Swap]) {
+        let mut one = self.compartment_one.chars().collect::<Vec<_>>();
+        let mut two = self.compartment_two.chars().collect::<Vec<_>>();
+        for swap in swaps {
+            std::mem::swap(&mut one[swap.one], &mut two[swap.two]);
+        }
+        self.compartment_one = one.into_iter().collect();
+        self.compartment_two = two.into_iter().collect();
+    }
+
+    /// Finds the fewest swaps that leave no item type in both compartments,
+    /// or `None` if the item counts make that impossible.
+    pub fn plan_reorganization(&self) -> Option<Reorganization> {
+        let mut counts = [(0, 0); 53];
+        for c in self.compartment_one.chars() {
+            counts[to_priority(c) as usize].0 += 1;
+        }
+        for c in self.compartment_two.chars() {
+            counts[to_priority(c) as usize].1 += 1;
+        }
+        let types = (1..=52)
+            .filter(|&p| counts[p] != (0, 0))
+            .collect::<Vec<_>>();
+        let size = self.compartment_one.len();
+
+        // best[i][n]: fewest items moved out of compartment one when the
+        // first i types are placed and n items ended up in compartment one.
+        let mut best = vec![vec![None; 2 * size + 1]; types.len() + 1];
+        best[0][0] = Some(0);
+        for (i, &p) in types.iter().enumerate() {
+            let (in_one, in_two) = counts[p];
+            for n in 0..=2 * size {
+                let Some(moved) = best[i][n] else {
+                    continue;
+                };
+                let to_one = &mut best[i + 1][n + in_one + in_two];
+                *to_one = Some(to_one.map_or(moved, |m: usize| m.min(moved)));
+                let to_two = &mut best[i + 1][n];
+                *to_two = Some(to_two.map_or(moved + in_one, |m: usize| m.min(moved + in_one)));
+            }
+        }
+        best[types.len()][size]?;
+
+        let mut to_one = ItemSet::new();
+        let mut n = size;
+        for (i, &p) in types.iter().enumerate().rev() {
+            let (in_one, in_two) = counts[p];
+            let moved = best[i + 1][n].unwrap();
+            if n >= in_one + in_two && best[i][n - in_one - in_two] == Some(moved) {
+                to_one.insert(from_priority(p as u32));
+                n -= in_one + in_two;
+            }
+        }
+
+        let leaving_one = self
+            .compartment_one
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| !to_one.contains(*c))
+            .map(|(idx, _)| idx);
+        let leaving_two = self
+            .compartment_two
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| to_one.contains(*c))
+            .map(|(idx, _)| idx);
+        Some(Reorganization {
+            misplaced: self.misplaced(),
+            swaps: leaving_one
+                .zip(leaving_two)
+                .map(|(one, two)| Swap { one, two })
+                .collect(),
+        })
+    }
+}
+
+/// A backpack whose compartments have no item in common.
+#[derive(PartialEq, Debug)]
+pub struct NoSharedItem {
+    pub line: usize,
+}
+
+impl fmt::Display for NoSharedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: compartments have no common item", self.line)
+    }
+}
+
+impl Error for NoSharedItem {}
+
 #[aoc(day3, part1)]
-pub fn solve_part1(input: &[Backpack]) -> u32 {
+pub fn solve_part1(input: &[Backpack]) -> Result<u32, NoSharedItem> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(idx, b)| {
+            ItemSet::from(b.compartment_one.as_str())
+                .intersection(&ItemSet::from(b.compartment_two.as_str()))
+                .priorities()
+                .next()
+                .ok_or(NoSharedItem { line: idx + 1 })
+        })
+        .sum()
+}
+
+/// A group whose backpacks don't share exactly one item. Groups are numbered
+/// from 1, like lines.
+#[derive(PartialEq, Debug)]
+pub enum BadgeError {
+    NoBadge { group: usize },
+    MultipleBadges { group: usize, badges: ItemSet },
+    IncompleteGroup { group: usize, size: usize },
+    EmptyGroups,
+}
+
+impl fmt::Display for BadgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BadgeError::NoBadge { group } => write!(f, "group {} has no common item", group),
+            BadgeError::MultipleBadges { group, badges } => write!(
+                f,
+                "group {} has {} common items: {}",
+                group,
+                badges.len(),
+                badges.iter().collect::<String>()
+            ),
+            BadgeError::IncompleteGroup { group, size } => {
+                write!(f, "group {} only has {} backpacks", group, size)
+            }
+            BadgeError::EmptyGroups => write!(f, "groups must have at least one backpack"),
+        }
+    }
+}
+
+impl Error for BadgeError {}
+
+pub fn group_items(input: &[Backpack], group_size: usize) -> Result<Vec<ItemSet>, BadgeError> {
+    if group_size == 0 {
+        return Err(BadgeError::EmptyGroups);
+    }
+    input
+        .chunks(group_size)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            if chunk.len() < group_size {
+                return Err(BadgeError::IncompleteGroup {
+                    group: idx + 1,
+                    size: chunk.len(),
+                });
+            }
+            Ok(chunk
+                .iter()
+                .map(Backpack::items)
+                .reduce(|acc, items| acc.intersection(&items))
+                .unwrap())
+        })
+        .collect()
+}
+
+pub fn group_badges(input: &[Backpack], group_size: usize) -> Result<Vec<char>, BadgeError> {
+    group_items(input, group_size)?
+        .into_iter()
+        .enumerate()
+        .map(|(idx, items)| match items.len() {
+            0 => Err(BadgeError::NoBadge { group: idx + 1 }),
+            1 => Ok(items.iter().next().unwrap()),
+            _ => Err(BadgeError::MultipleBadges {
+                group: idx + 1,
+                badges: items,
+            }),
+        })
+        .collect()
+}
+
+#[aoc(day3, part2)]
+pub fn solve_part2(input: &[Backpack]) -> Result<u32, BadgeError> {
+    Ok(group_badges(input, 3)?.into_iter().map(to_priority).sum())
+}
+
+#[aoc(day3, part1, hashset)]
+pub fn solve_part1_hashset(input: &[Backpack]) -> u32 {
     input
         .iter()
         .map(|b| {
@@ -60,8 +390,8 @@ pub fn solve_part1(input: &[Backpack]) -> u32 {
         .sum()
 }
 
-#[aoc(day3, part2)]
-pub fn solve_part2(input: &[Backpack]) -> u32 {
+#[aoc(day3, part2, hashset)]
+pub fn solve_part2_hashset(input: &[Backpack]) -> u32 {
     input
         .iter()
         .chunks(3)
@@ -106,6 +436,7 @@ pub fn solve_part2(input: &[Backpack]) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{timed, XorShift};
 
     const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
@@ -124,15 +455,15 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
             Backpack::new("ttgJtRGJ", "QctTZtZT"),
             Backpack::new("CrZsJsPPZsGz", "wwsLwLmpwMDw"),
         ];
-        let actual = generate(EXAMPLE);
+        let actual = generate(EXAMPLE).unwrap();
         assert_eq!(expected, actual)
     }
 
     #[test]
     fn test2() {
-        let input = generate(EXAMPLE);
+        let input = generate(EXAMPLE).unwrap();
 
-        let expected = 157;
+        let expected = Ok(157);
         let actual = solve_part1(&input);
 
         assert_eq!(expected, actual)
@@ -167,9 +498,223 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     fn test7() {
-        let expected = 70;
-        let actual = solve_part2(&generate(EXAMPLE));
+        let expected = Ok(70);
+        let actual = solve_part2(&generate(EXAMPLE).unwrap());
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test8() {
+        let first = ItemSet::from("abcXY");
+        let second = ItemSet::from("cdeYZ");
+
+        assert_eq!(ItemSet::from("abcdeXYZ"), first.union(&second));
+        assert_eq!(ItemSet::from("cY"), first.intersection(&second));
+        assert_eq!(ItemSet::from("abX"), first.difference(&second));
+    }
+
+    #[test]
+    fn test9() {
+        let set = ItemSet::from("ZzaAa");
+
+        assert_eq!(4, set.len());
+        assert!(set.contains('A'));
+        assert!(!set.contains('b'));
+        assert_eq!(vec!['a', 'z', 'A', 'Z'], set.iter().collect::<Vec<_>>());
+        assert_eq!(vec![1, 26, 27, 52], set.priorities().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test10() {
+        let input = generate(EXAMPLE).unwrap();
+
+        assert_eq!(Ok(solve_part1_hashset(&input)), solve_part1(&input));
+        assert_eq!(Ok(solve_part2_hashset(&input)), solve_part2(&input));
+    }
+
+    fn synthetic_input(groups: usize) -> String {
+        let letters = ('a'..='z').chain('A'..='Z').collect::<Vec<_>>();
+        let mut rng = XorShift::new(0x2545_f491_4f6c_dd1d);
+        let mut next = move |bound| rng.below(bound);
+        let mut out = String::new();
+        for _ in 0..groups {
+            let mut pool = letters.clone();
+            let badge = pool.swap_remove(next(pool.len()));
+            for backpack in pool.chunks(17) {
+                let (one, two) = backpack.split_at(8);
+                let len = 8 + next(24);
+                for half in [one, two] {
+                    let badge_at = next(len);
+                    for i in 0..len {
+                        out.push(if i == badge_at {
+                            badge
+                        } else {
+                            half[next(half.len())]
+                        });
+                    }
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored"]
+    fn bench_item_set() {
+        let input = generate(&synthetic_input(200_000)).unwrap();
+
+        let (expected, hashset) = timed(|| solve_part1_hashset(&input));
+        let (actual, bitset) = timed(|| solve_part1(&input));
+        assert_eq!(Ok(expected), actual);
+        assert!(
+            bitset < hashset,
+            "part 1: bitset {:?}, hashset {:?}",
+            bitset,
+            hashset
+        );
+
+        let (expected, hashset) = timed(|| solve_part2_hashset(&input));
+        let (actual, bitset) = timed(|| solve_part2(&input));
+        assert_eq!(Ok(expected), actual);
+        assert!(
+            bitset < hashset,
+            "part 2: bitset {:?}, hashset {:?}",
+            bitset,
+            hashset
+        );
+    }
+
+    #[test]
+    fn test11() {
+        let expected = Ok(vec!['r', 'Z']);
+        let actual = group_badges(&generate(EXAMPLE).unwrap(), 3);
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test12() {
+        let expected = Ok(vec![
+            ItemSet::from("rsfFM"),
+            ItemSet::from("vqwBT"),
+            ItemSet::from("ZGJ"),
+        ]);
+        let actual = group_items(&generate(EXAMPLE).unwrap(), 2);
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test13() {
+        let expected = Err(BadgeError::MultipleBadges {
+            group: 1,
+            badges: ItemSet::from("rsfFM"),
+        });
+        let actual = group_badges(&generate(EXAMPLE).unwrap(), 2);
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test14() {
+        let expected = Err(BadgeError::NoBadge { group: 1 });
+        let actual = group_badges(&generate(EXAMPLE).unwrap(), 6);
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test15() {
+        let expected = Err(BadgeError::IncompleteGroup { group: 2, size: 2 });
+        let actual = group_badges(&generate(EXAMPLE).unwrap(), 4);
+
+        assert_eq!(expected, actual);
+        assert_eq!(
+            "group 2 only has 2 backpacks",
+            actual.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test16() {
+        let expected = Err(ParseError::OddLength { line: 2, length: 5 });
+        let actual = generate("abcA\nabcde\nab");
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test17() {
+        let expected = Err(ParseError::InvalidItem {
+            line: 2,
+            column: 3,
+            item: 'é',
+        });
+        let actual = generate("abcA\naBéd");
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test18() {
+        let backpack = Backpack::new("vJrwpWtwJgWr", "hcsFMMfFFhFp");
+        let expected = Some(Reorganization {
+            misplaced: ItemSet::from("p"),
+            swaps: vec![Swap { one: 4, two: 2 }],
+        });
+        let actual = backpack.plan_reorganization();
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test19() {
+        for mut backpack in generate(EXAMPLE).unwrap() {
+            let plan = backpack.plan_reorganization().unwrap();
+            assert_eq!(backpack.misplaced(), plan.misplaced);
+            backpack.apply(&plan.swaps);
+            assert!(backpack.misplaced().is_empty());
+        }
+    }
+
+    #[test]
+    fn test20() {
+        let backpack = Backpack::new("aa", "ab");
+
+        assert_eq!(None, backpack.plan_reorganization())
+    }
+
+    #[test]
+    fn test21() {
+        let backpack = Backpack::new("ab", "cd");
+        let expected = Some(Reorganization {
+            misplaced: ItemSet::new(),
+            swaps: vec![],
+        });
+
+        assert_eq!(expected, backpack.plan_reorganization())
+    }
+
+    #[test]
+    fn test22() {
+        let input = generate(EXAMPLE).unwrap();
+
+        assert_eq!(Err(BadgeError::EmptyGroups), group_badges(&input, 0));
+        assert_eq!(Err(BadgeError::EmptyGroups), group_badges(&[], 0));
+    }
+
+    #[test]
+    fn test23() {
+        let input = generate("abcb\nab\n\naBcB").unwrap();
+
+        assert_eq!(Err(NoSharedItem { line: 2 }), solve_part1(&input));
+        assert_eq!(
+            "line 2: compartments have no common item",
+            solve_part1(&input).unwrap_err().to_string()
+        );
+        assert_eq!(Err(NoSharedItem { line: 1 }), solve_part1(&input[2..]));
+        assert_eq!(Ok(28), solve_part1(&input[3..]));
+    }
 }
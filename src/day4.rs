@@ -1,23 +1,210 @@
+use std::{collections::BTreeSet, fmt};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
-#[derive(PartialEq, Debug)]
-pub struct Range {
-    start: u32,
-    end: u32,
+pub trait Section: Copy + Ord + fmt::Debug {
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    fn offset(self) -> i128;
+}
+
+macro_rules! impl_section {
+    ($($t:ty),*) => {
+        $(
+            impl Section for $t {
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn offset(self) -> i128 {
+                    self as i128
+                }
+            }
+        )*
+    };
+}
+
+impl_section!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// An inclusive range of sections, `start..=end`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct Range<T = u32> {
+    start: T,
+    end: T,
 }
 
-impl Range {
-    fn new(start: u32, end: u32) -> Self {
+impl<T: Section> Range<T> {
+    pub fn new(start: T, end: T) -> Self {
+        assert!(start <= end, "range {:?}-{:?} is reversed", start, end);
         Self { start, end }
     }
 
-    fn covers(&self, other: &Self) -> bool {
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    // A range always holds at least one section, so there's no `is_empty`.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u128 {
+        (self.end.offset() - self.start.offset()) as u128 + 1
+    }
+
+    pub fn contains(&self, section: T) -> bool {
+        self.start <= section && section <= self.end
+    }
+
+    pub fn covers(&self, other: &Self) -> bool {
         self.start <= other.start && self.end >= other.end
     }
 
-    fn overlaps(&self, other: &Self) -> bool {
+    pub fn overlaps(&self, other: &Self) -> bool {
         self.start <= other.end && other.start <= self.end
     }
+
+    pub fn adjacent(&self, other: &Self) -> bool {
+        self.end.succ() == Some(other.start) || other.end.succ() == Some(self.start)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) {
+            Some(Self::new(
+                self.start.max(other.start),
+                self.end.min(other.end),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Merges two ranges into one, if they overlap or are adjacent.
+    pub fn union(&self, other: &Self) -> Option<Self> {
+        if self.overlaps(other) || self.adjacent(other) {
+            Some(Self::new(
+                self.start.min(other.start),
+                self.end.max(other.end),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The parts of `self` not in `other`, in order. There are at most two.
+    pub fn difference(&self, other: &Self) -> Vec<Self> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let mut out = Vec::new();
+        if self.start < other.start {
+            out.push(Self::new(self.start, other.start.pred().unwrap()));
+        }
+        if other.end < self.end {
+            out.push(Self::new(other.end.succ().unwrap(), self.end));
+        }
+        out
+    }
+}
+
+/// A set of sections, kept as sorted, disjoint and non-adjacent ranges.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct RangeSet<T = u32> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Section> RangeSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of sections in the set.
+    pub fn coverage(&self) -> u128 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        // Everything from `lo` up to `hi` overlaps or touches `range`.
+        let lo = self
+            .ranges
+            .partition_point(|r| r.end < range.start && !r.adjacent(&range));
+        let hi = self
+            .ranges
+            .partition_point(|r| r.start <= range.end || r.adjacent(&range));
+        let merged = self.ranges[lo..hi]
+            .iter()
+            .fold(range, |acc, r| acc.union(r).unwrap());
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let remaining = self.ranges[lo..hi]
+            .iter()
+            .flat_map(|r| r.difference(&range))
+            .collect::<Vec<_>>();
+        self.ranges.splice(lo..hi, remaining);
+    }
+
+    pub fn contains(&self, section: T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end < section);
+        self.ranges.get(idx).is_some_and(|r| r.contains(section))
+    }
+
+    pub fn covers(&self, range: &Range<T>) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end < range.start);
+        self.ranges.get(idx).is_some_and(|r| r.covers(range))
+    }
+
+    pub fn overlaps(&self, range: &Range<T>) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end < range.start);
+        self.ranges.get(idx).is_some_and(|r| r.overlaps(range))
+    }
+
+    /// The number of sections of `range` that are in the set.
+    pub fn coverage_of(&self, range: &Range<T>) -> u128 {
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        self.ranges[lo..hi]
+            .iter()
+            .filter_map(|r| r.intersection(range))
+            .map(|r| r.len())
+            .sum()
+    }
+}
+
+impl<T: Section> RangeSet<T> {
+    /// The ranges between the first and last section that are not in the set.
+    pub fn gaps(&self) -> Vec<Range<T>> {
+        self.ranges
+            .windows(2)
+            .map(|w| Range::new(w[0].end.succ().unwrap(), w[1].start.pred().unwrap()))
+            .collect()
+    }
+}
+
+impl<T: Section> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut out = Self::new();
+        for range in iter {
+            out.insert(range);
+        }
+        out
+    }
 }
 
 #[aoc_generator(day4)]
@@ -38,11 +225,184 @@ pub fn generate(input: &str) -> Vec<(Range, Range)> {
         .collect()
 }
 
+#[derive(PartialEq, Debug)]
+pub struct Analysis<T = u32> {
+    /// Every elf whose assignment overlaps at least one other elf's.
+    pub overlapping: Vec<usize>,
+    /// The most elves covering a single section, and the first such section.
+    pub max_coverage: Option<(usize, T)>,
+    /// Sections between the lowest and highest assigned one that nobody covers.
+    pub uncovered: Vec<Range<T>>,
+    /// Every pair of elves with overlapping assignments, lower index first.
+    pub pairs: Vec<(usize, usize)>,
+}
+
+/// Lists the assignment of every elf in the file, two per line.
+pub fn assignments<T: Section>(input: &[(Range<T>, Range<T>)]) -> Vec<Range<T>> {
+    input
+        .iter()
+        .flat_map(|(first, second)| [*first, *second])
+        .collect()
+}
+
+/// Sweeps over all assignments in start order, keeping the ones still
+/// active ordered by end so they can be retired as the sweep passes them.
+pub fn analyze<T: Section>(assignments: &[Range<T>]) -> Analysis<T> {
+    let mut order = (0..assignments.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&idx| assignments[idx].start);
+
+    let mut active = BTreeSet::<(T, usize)>::new();
+    let mut pairs = Vec::new();
+    let mut max_coverage: Option<(usize, T)> = None;
+    for idx in order {
+        let range = assignments[idx];
+        while let Some(&(end, _)) = active.first() {
+            if end >= range.start {
+                break;
+            }
+            active.pop_first();
+        }
+        for &(_, other) in &active {
+            pairs.push((other.min(idx), other.max(idx)));
+        }
+        active.insert((range.end, idx));
+        if max_coverage.is_none_or(|(count, _)| active.len() > count) {
+            max_coverage = Some((active.len(), range.start));
+        }
+    }
+
+    let mut overlapping = pairs
+        .iter()
+        .flat_map(|&(first, second)| [first, second])
+        .collect::<Vec<_>>();
+    overlapping.sort_unstable();
+    overlapping.dedup();
+
+    Analysis {
+        overlapping,
+        max_coverage,
+        uncovered: assignments.iter().copied().collect::<RangeSet<T>>().gaps(),
+        pairs,
+    }
+}
+
+/// A static interval tree over a list of assignments. The ranges are kept
+/// sorted by start as an implicit balanced tree, where each subtree also
+/// records the highest end below it so whole subtrees can be skipped.
+#[derive(Debug, Clone)]
+pub struct IntervalTree<T = u32> {
+    entries: Vec<(Range<T>, usize)>,
+    max_end: Vec<T>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Query<T = u32> {
+    Contains(T),
+    Intersects(Range<T>),
+}
+
+impl<T: Section> IntervalTree<T> {
+    pub fn new(assignments: &[Range<T>]) -> Self {
+        let mut entries = assignments
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(idx, range)| (range, idx))
+            .collect::<Vec<_>>();
+        entries.sort();
+        let mut max_end = entries.iter().map(|(range, _)| range.end).collect();
+        Self::build(&entries, &mut max_end, 0, entries.len());
+        Self { entries, max_end }
+    }
+
+    fn build(
+        entries: &[(Range<T>, usize)],
+        max_end: &mut Vec<T>,
+        lo: usize,
+        hi: usize,
+    ) -> Option<T> {
+        if lo == hi {
+            return None;
+        }
+        let mid = (lo + hi) / 2;
+        let left = Self::build(entries, max_end, lo, mid);
+        let right = Self::build(entries, max_end, mid + 1, hi);
+        max_end[mid] = [left, right]
+            .into_iter()
+            .flatten()
+            .fold(entries[mid].0.end, T::max);
+        Some(max_end[mid])
+    }
+
+    /// Every assignment containing `section`, in order of start.
+    pub fn containing(&self, section: T) -> Vec<usize> {
+        self.intersecting(&Range::new(section, section))
+    }
+
+    /// Every assignment sharing a section with `range`, in order of start.
+    pub fn intersecting(&self, range: &Range<T>) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.collect(range, 0, self.entries.len(), &mut out);
+        out
+    }
+
+    pub fn answer(&self, query: &Query<T>) -> Vec<usize> {
+        match query {
+            Query::Contains(section) => self.containing(*section),
+            Query::Intersects(range) => self.intersecting(range),
+        }
+    }
+
+    fn collect(&self, range: &Range<T>, lo: usize, hi: usize, out: &mut Vec<usize>) {
+        if lo == hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if self.max_end[mid] < range.start {
+            return;
+        }
+        self.collect(range, lo, mid, out);
+        let (entry, idx) = &self.entries[mid];
+        if entry.start > range.end {
+            return;
+        }
+        if entry.overlaps(range) {
+            out.push(*idx);
+        }
+        self.collect(range, mid + 1, hi, out);
+    }
+}
+
+/// Parses one query per line, either a single section (`6`) or a range of
+/// sections (`2-4`).
+pub fn parse_queries(input: &str) -> Result<Vec<Query>, String> {
+    input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let parse = |n: &str| n.parse().map_err(|_| format!("invalid query: {}", l));
+            match l.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        return Err(format!("invalid query: {}", l));
+                    }
+                    Ok(Query::Intersects(Range::new(start, end)))
+                }
+                None => Ok(Query::Contains(parse(l)?)),
+            }
+        })
+        .collect()
+}
+
 #[aoc(day4, part1)]
 pub fn solve_part1(input: &[(Range, Range)]) -> u32 {
     input
         .iter()
-        .filter(|(first, second)| first.covers(second) || second.covers(first))
+        .filter(|(first, second)| {
+            let set = RangeSet::from_iter([*first, *second]);
+            set.coverage() == first.len().max(second.len())
+        })
         .count() as u32
 }
 
@@ -50,7 +410,10 @@ pub fn solve_part1(input: &[(Range, Range)]) -> u32 {
 pub fn solve_part2(input: &[(Range, Range)]) -> u32 {
     input
         .iter()
-        .filter(|(first, second)| first.overlaps(second))
+        .filter(|(first, second)| {
+            let set = RangeSet::from_iter([*first, *second]);
+            set.coverage() < first.len() + second.len()
+        })
         .count() as u32
 }
 
@@ -111,4 +474,176 @@ mod tests {
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test6() {
+        let first = Range::new(2, 6);
+        let second = Range::new(4, 8);
+
+        assert_eq!(Some(Range::new(4, 6)), first.intersection(&second));
+        assert_eq!(Some(Range::new(2, 8)), first.union(&second));
+        assert_eq!(vec![Range::new(2, 3)], first.difference(&second));
+        assert_eq!(5, first.len());
+    }
+
+    #[test]
+    fn test7() {
+        let first = Range::new(2, 3);
+        let second = Range::new(4, 5);
+        let third = Range::new(6, 6);
+
+        assert_eq!(None, first.intersection(&second));
+        assert_eq!(Some(Range::new(2, 5)), first.union(&second));
+        assert_eq!(None, first.union(&third));
+    }
+
+    #[test]
+    fn test8() {
+        let outer = Range::new(2u8, 8);
+        let inner = Range::new(4, 5);
+
+        assert_eq!(
+            vec![Range::new(2, 3), Range::new(6, 8)],
+            outer.difference(&inner)
+        );
+        assert_eq!(Vec::<Range<u8>>::new(), inner.difference(&outer));
+    }
+
+    #[test]
+    fn test9() {
+        let mut set = RangeSet::new();
+        set.insert(Range::new(10, 12));
+        set.insert(Range::new(1, 3));
+        set.insert(Range::new(6, 7));
+        set.insert(Range::new(4, 4));
+
+        assert_eq!(
+            &[Range::new(1, 4), Range::new(6, 7), Range::new(10, 12)],
+            set.ranges()
+        );
+
+        set.insert(Range::new(5, 10));
+
+        assert_eq!(&[Range::new(1, 12)], set.ranges());
+        assert_eq!(12, set.coverage());
+    }
+
+    #[test]
+    fn test10() {
+        let mut set = RangeSet::from_iter([Range::new(-10i64, 10), Range::new(20, 30)]);
+        set.remove(Range::new(-5, 25));
+
+        assert_eq!(&[Range::new(-10, -6), Range::new(26, 30)], set.ranges());
+        assert!(set.contains(-6));
+        assert!(!set.contains(0));
+        assert!(set.contains(30));
+        assert!(!set.contains(31));
+        assert!(set.covers(&Range::new(27, 29)));
+        assert!(!set.covers(&Range::new(-7, 26)));
+        assert!(set.overlaps(&Range::new(-7, 26)));
+        assert_eq!(4, set.coverage_of(&Range::new(-7, 27)));
+    }
+
+    #[test]
+    fn test11() {
+        let assignments = assignments(&generate(EXAMPLE));
+        let analysis = analyze(&assignments);
+
+        let mut expected = Vec::new();
+        for (i, first) in assignments.iter().enumerate() {
+            for (j, second) in assignments.iter().enumerate().skip(i + 1) {
+                if first.overlaps(second) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        let mut actual = analysis.pairs.clone();
+        actual.sort();
+
+        assert_eq!(expected, actual);
+        assert_eq!((0..12).collect::<Vec<_>>(), analysis.overlapping);
+        assert_eq!(Some((8, 6)), analysis.max_coverage);
+        assert!(analysis.uncovered.is_empty());
+    }
+
+    #[test]
+    fn test12() {
+        let assignments = vec![
+            Range::new(1, 3),
+            Range::new(10, 12),
+            Range::new(3, 4),
+            Range::new(7, 7),
+            Range::new(12, 20),
+        ];
+        let expected = Analysis {
+            overlapping: vec![0, 1, 2, 4],
+            max_coverage: Some((2, 3)),
+            uncovered: vec![Range::new(5, 6), Range::new(8, 9)],
+            pairs: vec![(0, 2), (1, 4)],
+        };
+
+        assert_eq!(expected, analyze(&assignments))
+    }
+
+    #[test]
+    fn test13() {
+        let expected = Analysis {
+            overlapping: vec![],
+            max_coverage: None,
+            uncovered: vec![],
+            pairs: vec![],
+        };
+
+        assert_eq!(expected, analyze::<u32>(&[]))
+    }
+
+    #[test]
+    fn test14() {
+        let assignments = assignments(&generate(EXAMPLE));
+        let tree = IntervalTree::new(&assignments);
+
+        for section in 0..11 {
+            let mut expected = (0..assignments.len())
+                .filter(|&idx| assignments[idx].contains(section))
+                .collect::<Vec<_>>();
+            let mut actual = tree.containing(section);
+            expected.sort();
+            actual.sort();
+            assert_eq!(expected, actual, "section {}", section);
+        }
+        for start in 0..11 {
+            for end in start..11 {
+                let range = Range::new(start, end);
+                let mut expected = (0..assignments.len())
+                    .filter(|&idx| assignments[idx].overlaps(&range))
+                    .collect::<Vec<_>>();
+                let mut actual = tree.intersecting(&range);
+                expected.sort();
+                actual.sort();
+                assert_eq!(expected, actual, "range {:?}", range);
+            }
+        }
+    }
+
+    #[test]
+    fn test15() {
+        let tree = IntervalTree::new(&assignments(&generate(EXAMPLE)));
+
+        assert_eq!(vec![2, 0, 10, 6], tree.containing(2));
+        assert_eq!(vec![5], tree.answer(&Query::Intersects(Range::new(9, 20))));
+        assert!(IntervalTree::<u32>::new(&[]).containing(1).is_empty());
+    }
+
+    #[test]
+    fn test16() {
+        let expected = Ok(vec![
+            Query::Contains(6),
+            Query::Intersects(Range::new(2, 4)),
+        ]);
+        let actual = parse_queries("6\n2-4\n");
+
+        assert_eq!(expected, actual);
+        assert!(parse_queries("4-2").is_err());
+        assert!(parse_queries("a").is_err());
+    }
 }
@@ -1,17 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+    fmt,
+    num::NonZeroUsize,
+};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
-#[derive(PartialEq, Debug, Clone)]
-struct Move {
-    times: usize,
-    source: usize,
-    destination: usize,
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Move {
+    pub times: usize,
+    pub source: usize,
+    pub destination: usize,
 }
 
 impl Move {
-    fn new(times: usize, source: usize, destination: usize) -> Self {
+    pub fn new(times: usize, source: usize, destination: usize) -> Self {
         Self {
             times,
             source,
@@ -20,24 +25,86 @@ impl Move {
     }
 }
 
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.times, self.source, self.destination
+        )
+    }
+}
+
+/// The crate stacks, numbered from 1 and stored densely so a move can lift
+/// a whole slice of crates at once.
+#[derive(PartialEq, Eq, Hash, Debug, Default, Clone)]
+pub struct Stacks {
+    stacks: Vec<Vec<String>>,
+}
+
+impl Stacks {
+    /// The number of the highest stack.
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Vec<String>> {
+        idx.checked_sub(1).and_then(|i| self.stacks.get(i))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Vec<String>)> {
+        self.stacks.iter().enumerate().map(|(i, s)| (i + 1, s))
+    }
+
+    fn stack_mut(&mut self, idx: usize) -> &mut Vec<String> {
+        if idx > self.stacks.len() {
+            self.stacks.resize_with(idx, Default::default);
+        }
+        &mut self.stacks[idx - 1]
+    }
+
+    /// Removes the top `count` crates of a stack, bottom-most first.
+    pub fn lift(&mut self, idx: usize, count: usize) -> Vec<String> {
+        let stack = self.stack_mut(idx);
+        stack.split_off(stack.len() - count)
+    }
+
+    /// Puts crates on top of a stack, bottom-most first.
+    pub fn put(&mut self, idx: usize, crates: impl IntoIterator<Item = String>) {
+        self.stack_mut(idx).extend(crates);
+    }
+}
+
+impl<T: Into<String>> From<Vec<Vec<T>>> for Stacks {
+    fn from(stacks: Vec<Vec<T>>) -> Self {
+        Self {
+            stacks: stacks
+                .into_iter()
+                .map(|s| s.into_iter().map(Into::into).collect())
+                .collect(),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Default)]
 pub struct Input {
-    stacks: HashMap<usize, Vec<char>>,
+    stacks: Stacks,
     moves: Vec<Move>,
 }
 
 impl Input {
-    fn new(stacks: HashMap<usize, Vec<char>>, moves: Vec<Move>) -> Self {
+    pub fn new(stacks: Stacks, moves: Vec<Move>) -> Self {
         Self { stacks, moves }
     }
 }
 
 impl Clone for Input {
     fn clone(&self) -> Self {
-        Self::new(
-            self.stacks.clone(),
-            self.moves.iter().map(|x| x.clone()).collect(),
-        )
+        Self::new(self.stacks.clone(), self.moves.to_vec())
     }
 }
 
@@ -45,31 +112,30 @@ impl Clone for Input {
 pub fn generate(input: &str) -> Input {
     let mut out: Input = Default::default();
     let mut lines = input.lines();
-    let mut line = lines.next().unwrap();
-    while !line.is_empty() {
-        for (idx, mut chunk) in line
-            .chars()
-            .enumerate()
-            .filter(|(i, _)| i % 4 != 3 || i == &0)
-            .map(|(_, item)| item)
-            .chunks(3)
-            .into_iter()
-            .enumerate()
-        {
-            let idx = idx + 1;
-            if chunk.next().unwrap() == ' ' {
-                continue;
-            }
-            if !out.stacks.contains_key(&idx) {
-                out.stacks.insert(idx, Default::default());
-            }
-            out.stacks
-                .get_mut(&idx)
-                .unwrap()
-                .insert(0, chunk.next().unwrap());
+    let diagram = lines
+        .by_ref()
+        .take_while(|l| !l.is_empty())
+        .collect::<Vec<_>>();
+    let (footer, rows) = diagram.split_last().unwrap();
+
+    // Each crate goes on the stack whose number in the footer is closest to
+    // it, so columns can be any width.
+    let columns = tokens(footer, ' ', ' ')
+        .map(|(center, label)| (center, label.parse::<usize>().unwrap()))
+        .collect::<Vec<_>>();
+    for &(_, idx) in &columns {
+        out.stacks.stack_mut(idx);
+    }
+    for row in rows.iter().rev() {
+        for (center, label) in tokens(row, '[', ']') {
+            let (_, idx) = columns
+                .iter()
+                .min_by_key(|(column, _)| column.abs_diff(center))
+                .unwrap();
+            out.stacks.stack_mut(*idx).push(label.to_string());
         }
-        line = lines.next().unwrap();
     }
+
     for line in lines {
         let mut iter = line.split(' ').skip(1);
         let times = iter.next().unwrap().parse().unwrap();
@@ -82,54 +148,399 @@ pub fn generate(input: &str) -> Input {
     out
 }
 
-#[aoc(day5, part1)]
-pub fn solve_part1(input: &Input) -> String {
-    let mut input = input.clone();
-    for m in &input.moves {
-        for _ in 0..m.times {
-            let from_source = input.stacks.get_mut(&m.source).unwrap().pop().unwrap();
-            input
-                .stacks
-                .get_mut(&m.destination)
-                .unwrap()
-                .push(from_source);
+/// Finds the labels in a diagram row that are wrapped in `open` and `close`,
+/// or delimited by them in the case of the footer's spaces. The position of
+/// each is given as twice its center, to keep it whole.
+fn tokens(line: &str, open: char, close: char) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = line.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while rest.next_if(|&(_, c)| c == open || c == ' ').is_some() {}
+        let (start, _) = *rest.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = rest.next_if(|&(_, c)| c != close && c != ' ') {
+            end = i + c.len_utf8();
         }
+        rest.next_if(|&(_, c)| c == close);
+        Some((start + end, &line[start..end]))
+    })
+}
+
+/// Describes how a crane carries out a `Move` on the stacks.
+pub trait Crane {
+    fn apply(&self, stacks: &mut Stacks, m: &Move);
+}
+
+/// Lifts one crate at a time, reversing their order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn apply(&self, stacks: &mut Stacks, m: &Move) {
+        let lifted = stacks.lift(m.source, m.times);
+        stacks.put(m.destination, lifted.into_iter().rev());
     }
-    let mut out = String::new();
-    for (_, stack) in input.stacks.iter().sorted_by_key(|(k, _)| *k) {
-        out.push(*stack.last().unwrap())
+}
+
+/// Lifts all crates of a move at once, keeping their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn apply(&self, stacks: &mut Stacks, m: &Move) {
+        let lifted = stacks.lift(m.source, m.times);
+        stacks.put(m.destination, lifted);
+    }
+}
+
+/// A CrateMover 9001 that can lift at most `capacity` crates at once, so
+/// larger moves are split into several lifts.
+pub struct CappedCrane {
+    pub capacity: NonZeroUsize,
+}
+
+impl CappedCrane {
+    /// A crane of the given capacity, unless it can't lift anything.
+    pub fn new(capacity: usize) -> Option<Self> {
+        NonZeroUsize::new(capacity).map(|capacity| Self { capacity })
+    }
+}
+
+impl Crane for CappedCrane {
+    fn apply(&self, stacks: &mut Stacks, m: &Move) {
+        let mut remaining = m.times;
+        while remaining > 0 {
+            let lift = remaining.min(self.capacity.get());
+            CrateMover9001.apply(stacks, &Move::new(lift, m.source, m.destination));
+            remaining -= lift;
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum MoveErrorKind {
+    MissingStack(usize),
+    NotEnoughCrates { available: usize },
+    SameStack,
+}
+
+/// A move that can't be carried out. `index` is the position of the move in
+/// the input, starting at zero, and `stacks` the state just before it.
+#[derive(PartialEq, Debug)]
+pub struct MoveError {
+    pub index: usize,
+    pub m: Move,
+    pub kind: MoveErrorKind,
+    pub stacks: Stacks,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move #{} ({}): ", self.index, self.m)?;
+        match self.kind {
+            MoveErrorKind::MissingStack(stack) => write!(f, "there is no stack {}", stack)?,
+            MoveErrorKind::NotEnoughCrates { available } => {
+                write!(f, "stack {} only has {} crates", self.m.source, available)?
+            }
+            MoveErrorKind::SameStack => write!(f, "source and destination are the same")?,
+        }
+        write!(f, "; stacks are")?;
+        for (idx, stack) in self.stacks.iter() {
+            write!(f, " {}: [{}]", idx, stack.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for MoveError {}
+
+fn validate(stacks: &Stacks, m: &Move) -> Result<(), MoveErrorKind> {
+    for stack in [m.source, m.destination] {
+        if stacks.get(stack).is_none() {
+            return Err(MoveErrorKind::MissingStack(stack));
+        }
+    }
+    if m.source == m.destination {
+        return Err(MoveErrorKind::SameStack);
+    }
+    let available = stacks.get(m.source).unwrap().len();
+    if available < m.times {
+        return Err(MoveErrorKind::NotEnoughCrates { available });
+    }
+    Ok(())
+}
+
+pub fn simulate(input: &Input, crane: &impl Crane) -> Result<Stacks, MoveError> {
+    simulate_steps(input, crane, |_, _| {})
+}
+
+/// Like `simulate`, but calls `observe` with each move and the stacks right
+/// after it has been applied.
+pub fn simulate_steps(
+    input: &Input,
+    crane: &impl Crane,
+    mut observe: impl FnMut(&Move, &Stacks),
+) -> Result<Stacks, MoveError> {
+    let mut stacks = input.stacks.clone();
+    for (index, m) in input.moves.iter().enumerate() {
+        if let Err(kind) = validate(&stacks, m) {
+            return Err(MoveError {
+                index,
+                m: m.clone(),
+                kind,
+                stacks,
+            });
+        }
+        crane.apply(&mut stacks, m);
+        observe(m, &stacks);
+    }
+    Ok(stacks)
+}
+
+struct Undo {
+    lifted: Vec<String>,
+    destination_height: usize,
+}
+
+/// Steps through the moves of an input in either direction, keeping an undo
+/// log so earlier states don't have to be simulated again from the start.
+pub struct Replay<'a, C> {
+    input: &'a Input,
+    crane: C,
+    stacks: Stacks,
+    undo: Vec<Undo>,
+}
+
+impl<'a, C: Crane> Replay<'a, C> {
+    pub fn new(input: &'a Input, crane: C) -> Self {
+        Self {
+            input,
+            crane,
+            stacks: input.stacks.clone(),
+            undo: Vec::new(),
+        }
+    }
+
+    /// The number of moves applied so far.
+    pub fn position(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    pub fn top(&self) -> String {
+        top_of_stacks(&self.stacks)
+    }
+
+    /// Applies the next move. Returns `false` if all moves have been applied.
+    pub fn step_forward(&mut self) -> Result<bool, MoveError> {
+        let index = self.position();
+        let Some(m) = self.input.moves.get(index) else {
+            return Ok(false);
+        };
+        if let Err(kind) = validate(&self.stacks, m) {
+            return Err(MoveError {
+                index,
+                m: m.clone(),
+                kind,
+                stacks: self.stacks.clone(),
+            });
+        }
+        let source = self.stacks.get(m.source).unwrap();
+        let lifted = source[source.len() - m.times..].to_vec();
+        let destination_height = self.stacks.get(m.destination).unwrap().len();
+        self.crane.apply(&mut self.stacks, m);
+        self.undo.push(Undo {
+            lifted,
+            destination_height,
+        });
+        Ok(true)
+    }
+
+    /// Reverts the last applied move. Returns `false` if there is none.
+    pub fn step_backward(&mut self) -> bool {
+        let Some(undo) = self.undo.pop() else {
+            return false;
+        };
+        let m = &self.input.moves[self.undo.len()];
+        let height = self.stacks.get(m.destination).unwrap().len();
+        self.stacks
+            .lift(m.destination, height - undo.destination_height);
+        self.stacks.put(m.source, undo.lifted);
+        true
+    }
+
+    /// Moves forward or backward until exactly `position` moves are applied,
+    /// or as close as the input allows.
+    pub fn jump(&mut self, position: usize) -> Result<(), MoveError> {
+        while self.position() > position {
+            self.step_backward();
+        }
+        while self.position() < position && self.step_forward()? {}
+        Ok(())
+    }
+
+    pub fn top_at(&mut self, position: usize) -> Result<String, MoveError> {
+        self.jump(position)?;
+        Ok(self.top())
+    }
+}
+
+pub fn top_of_stacks(stacks: &Stacks) -> String {
+    stacks
+        .iter()
+        .filter_map(|(_, stack)| stack.last().map(String::as_str))
+        .collect()
+}
+
+/// Searches breadth-first for the fewest moves that make `crane` leave
+/// `target` as the top of the stacks, trying at most `budget` moves.
+pub fn plan(stacks: &Stacks, target: &str, crane: &impl Crane, budget: usize) -> Option<Vec<Move>> {
+    let mut states = vec![(stacks.clone(), None::<(usize, Move)>)];
+    let mut seen = HashSet::from([stacks.clone()]);
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some((idx, depth)) = queue.pop_front() {
+        if top_of_stacks(&states[idx].0) == target {
+            let mut moves = Vec::new();
+            let mut current = idx;
+            while let Some((parent, m)) = &states[current].1 {
+                moves.push(m.clone());
+                current = *parent;
+            }
+            moves.reverse();
+            return Some(moves);
+        }
+        if depth == budget {
+            continue;
+        }
+        let current = states[idx].0.clone();
+        for (source, stack) in current.iter() {
+            for destination in 1..=current.len() {
+                if destination == source {
+                    continue;
+                }
+                for times in 1..=stack.len() {
+                    let m = Move::new(times, source, destination);
+                    let mut next = current.clone();
+                    crane.apply(&mut next, &m);
+                    if seen.insert(next.clone()) {
+                        queue.push_back((states.len(), depth + 1));
+                        states.push((next, Some((idx, m))));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Draws the stacks as the crate diagram from the puzzle input, numbered
+/// footer included.
+pub fn render(stacks: &Stacks) -> String {
+    let widths = stacks
+        .iter()
+        .map(|(idx, stack)| {
+            stack
+                .iter()
+                .map(|label| label.len() + 2)
+                .chain([idx.to_string().len(), 3])
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let center = |text: &str, width: usize| {
+        let left = (width - text.len()) / 2;
+        format!(
+            "{:left$}{}{:right$}",
+            "",
+            text,
+            "",
+            right = width - left - text.len()
+        )
+    };
+    let height = stacks.iter().map(|(_, s)| s.len()).max().unwrap_or(0);
+    let mut rows = Vec::new();
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .zip(&widths)
+            .map(|((_, stack), &width)| match stack.get(level) {
+                Some(label) => center(&format!("[{}]", label), width),
+                None => center("", width),
+            })
+            .join(" ");
+        rows.push(row);
+    }
+    rows.push(
+        stacks
+            .iter()
+            .zip(&widths)
+            .map(|((idx, _), &width)| center(&idx.to_string(), width))
+            .join(" "),
+    );
+    rows.join("\n")
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", render(&self.stacks))?;
+        for m in &self.moves {
+            write!(f, "\n{}", m)?;
+        }
+        Ok(())
     }
-    out
+}
+
+#[aoc(day5, part1)]
+pub fn solve_part1(input: &Input) -> Result<String, MoveError> {
+    Ok(top_of_stacks(&simulate(input, &CrateMover9000)?))
 }
 
 #[aoc(day5, part2)]
-pub fn solve_part2(input: &Input) -> String {
-    let mut input = input.clone();
+pub fn solve_part2(input: &Input) -> Result<String, MoveError> {
+    Ok(top_of_stacks(&simulate(input, &CrateMover9001)?))
+}
+
+// The crate-by-crate, HashMap-backed simulation this module used to do,
+// kept around as the `hashmap` variant for benchmarking.
+fn simulate_hashmap(input: &Input, keep_order: bool) -> String {
+    let mut stacks = input
+        .stacks
+        .iter()
+        .map(|(idx, stack)| (idx, stack.clone()))
+        .collect::<HashMap<_, _>>();
     for m in &input.moves {
-        let mut from_source_buf = Vec::<char>::default();
+        let mut from_source_buf = Vec::<String>::default();
         for _ in 0..m.times {
-            from_source_buf.push(input.stacks.get_mut(&m.source).unwrap().pop().unwrap())
+            from_source_buf.push(stacks.get_mut(&m.source).unwrap().pop().unwrap())
         }
-        while !from_source_buf.is_empty() {
-            input
-                .stacks
-                .get_mut(&m.destination)
-                .unwrap()
-                .push(from_source_buf.pop().unwrap());
+        if !keep_order {
+            from_source_buf.reverse();
+        }
+        while let Some(element) = from_source_buf.pop() {
+            stacks.get_mut(&m.destination).unwrap().push(element);
         }
     }
-    let mut out = String::new();
-    for (_, stack) in input.stacks.iter().sorted_by_key(|(k, _)| *k) {
-        out.push(*stack.last().unwrap())
-    }
-    out
+    stacks
+        .iter()
+        .sorted_by_key(|(k, _)| *k)
+        .filter_map(|(_, stack)| stack.last().map(String::as_str))
+        .collect()
+}
+
+#[aoc(day5, part1, hashmap)]
+pub fn solve_part1_hashmap(input: &Input) -> String {
+    simulate_hashmap(input, false)
+}
+
+#[aoc(day5, part2, hashmap)]
+pub fn solve_part2_hashmap(input: &Input) -> String {
+    simulate_hashmap(input, true)
 }
 
 #[cfg(test)]
 mod tests {
-    use maplit::hashmap;
-
     use super::*;
+    use crate::testing::{timed, XorShift};
 
     static EXAMPLE: &str = "    [D]    
 [N] [C]    
@@ -144,11 +555,7 @@ move 1 from 1 to 2";
     #[test]
     fn test1() {
         let expected = Input::new(
-            hashmap! {
-                1 => vec!['Z','N',],
-                2 => vec!['M','C','D',],
-                3 => vec!['P']
-            },
+            Stacks::from(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]),
             vec![
                 Move::new(1, 2, 1),
                 Move::new(3, 1, 3),
@@ -164,7 +571,7 @@ move 1 from 1 to 2";
 
     #[test]
     fn test2() {
-        let expected = "CMZ".to_string();
+        let expected = Ok("CMZ".to_string());
         let actual = solve_part1(&generate(EXAMPLE));
 
         assert_eq!(expected, actual)
@@ -172,9 +579,318 @@ move 1 from 1 to 2";
 
     #[test]
     fn test3() {
-        let expected = "MCD".to_string();
+        let expected = Ok("MCD".to_string());
         let actual = solve_part2(&generate(EXAMPLE));
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test4() {
+        let input = generate(EXAMPLE);
+
+        assert_eq!(
+            "MCD",
+            top_of_stacks(&simulate(&input, &CappedCrane::new(3).unwrap()).unwrap())
+        );
+        assert_eq!(
+            "MCD",
+            top_of_stacks(&simulate(&input, &CappedCrane::new(5).unwrap()).unwrap())
+        );
+        assert_eq!(
+            "CMZ",
+            top_of_stacks(&simulate(&input, &CappedCrane::new(1).unwrap()).unwrap())
+        );
+    }
+
+    #[test]
+    fn test5() {
+        let input = generate(EXAMPLE);
+        let expected = Stacks::from(vec![vec!['M'], vec!['C'], vec!['P', 'N', 'D', 'Z']]);
+
+        assert_eq!(
+            Ok(expected),
+            simulate(&input, &CappedCrane::new(2).unwrap())
+        )
+    }
+
+    fn with_moves(moves: Vec<Move>) -> Input {
+        Input::new(
+            Stacks::from(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]),
+            moves,
+        )
+    }
+
+    #[test]
+    fn test6() {
+        let input = with_moves(vec![Move::new(1, 2, 1), Move::new(1, 4, 1)]);
+        let expected = Err(MoveError {
+            index: 1,
+            m: Move::new(1, 4, 1),
+            kind: MoveErrorKind::MissingStack(4),
+            stacks: Stacks::from(vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]),
+        });
+
+        assert_eq!(expected, simulate(&input, &CrateMover9000))
+    }
+
+    #[test]
+    fn test7() {
+        let input = with_moves(vec![Move::new(1, 1, 0)]);
+        let actual = simulate(&input, &CrateMover9000).unwrap_err();
+
+        assert_eq!(MoveErrorKind::MissingStack(0), actual.kind)
+    }
+
+    #[test]
+    fn test8() {
+        let input = with_moves(vec![Move::new(2, 1, 3), Move::new(1, 1, 2)]);
+        let actual = simulate(&input, &CrateMover9001).unwrap_err();
+
+        assert_eq!(1, actual.index);
+        assert_eq!(MoveErrorKind::NotEnoughCrates { available: 0 }, actual.kind);
+        assert_eq!(
+            "move #1 (move 1 from 1 to 2): stack 1 only has 0 crates; stacks are 1: [] 2: [M C D] 3: [P Z N]",
+            actual.to_string()
+        );
+    }
+
+    #[test]
+    fn test9() {
+        let input = with_moves(vec![Move::new(1, 3, 3)]);
+        let actual = simulate(&input, &CrateMover9000).unwrap_err();
+
+        assert_eq!(0, actual.index);
+        assert_eq!(MoveErrorKind::SameStack, actual.kind);
+    }
+
+    #[test]
+    fn test10() {
+        let input = generate(EXAMPLE);
+        let (diagram, _) = EXAMPLE.split_once("\n\n").unwrap();
+
+        assert_eq!(diagram, render(&input.stacks));
+        assert_eq!(EXAMPLE, input.to_string());
+        assert_eq!(input, generate(&input.to_string()));
+    }
+
+    #[test]
+    fn test11() {
+        let mut steps = Vec::new();
+        simulate_steps(&generate(EXAMPLE), &CrateMover9001, |m, stacks| {
+            steps.push(format!("{}\n{}", m, render(stacks)))
+        })
+        .unwrap();
+
+        assert_eq!(4, steps.len());
+        assert_eq!(
+            "move 3 from 1 to 3
+        [D]
+        [N]
+    [C] [Z]
+    [M] [P]
+ 1   2   3 ",
+            steps[1]
+        );
+    }
+
+    fn synthetic_input(stacks: usize, moves: usize) -> Input {
+        let mut rng = XorShift::new(0x9e37_79b9_7f4a_7c15);
+        let mut next = move |bound| rng.below(bound);
+        let mut heights = vec![100; stacks];
+        let initial = (0..stacks)
+            .map(|_| (0..100).map(|_| (b'A' + next(26) as u8) as char).collect())
+            .collect::<Vec<_>>();
+        let mut out = Vec::with_capacity(moves);
+        while out.len() < moves {
+            let (source, destination) = (next(stacks), next(stacks));
+            if source == destination || heights[source] == 0 {
+                continue;
+            }
+            let times = 1 + next(heights[source].min(50));
+            heights[source] -= times;
+            heights[destination] += times;
+            out.push(Move::new(times, source + 1, destination + 1));
+        }
+        Input::new(Stacks::from(initial), out)
+    }
+
+    #[test]
+    fn test12() {
+        let input = synthetic_input(20, 1000);
+
+        assert_eq!(solve_part1(&input), Ok(solve_part1_hashmap(&input)));
+        assert_eq!(solve_part2(&input), Ok(solve_part2_hashmap(&input)));
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored"]
+    fn bench_stacks() {
+        let input = synthetic_input(5_000, 2_000_000);
+        let parts = [
+            (
+                solve_part1 as fn(&Input) -> _,
+                solve_part1_hashmap as fn(&Input) -> _,
+            ),
+            (solve_part2, solve_part2_hashmap),
+        ];
+
+        for (part, (solve, solve_hashmap)) in parts.into_iter().enumerate() {
+            let (expected, hashmap) = timed(|| solve_hashmap(&input));
+            let (actual, dense) = timed(|| solve(&input));
+            assert_eq!(Ok(expected), actual);
+            assert!(
+                dense < hashmap,
+                "part {}: dense {:?}, hashmap {:?}",
+                part + 1,
+                dense,
+                hashmap
+            );
+        }
+    }
+
+    #[test]
+    fn test13() {
+        let input = "[AB]      [E]
+[CD] [XYZ] [F]
+ 1     2    3
+
+move 1 from 2 to 1
+move 2 from 1 to 3";
+        let expected = Input::new(
+            Stacks::from(vec![vec!["CD", "AB"], vec!["XYZ"], vec!["F", "E"]]),
+            vec![Move::new(1, 2, 1), Move::new(2, 1, 3)],
+        );
+
+        assert_eq!(expected, generate(input));
+        assert_eq!(Ok("CDXYZ".to_string()), solve_part2(&generate(input)));
+    }
+
+    #[test]
+    fn test14() {
+        let stacks = Stacks::from(
+            (1..=12)
+                .map(|i| (0..i % 4).map(|j| format!("{}{}", i, j)).collect())
+                .collect::<Vec<Vec<String>>>(),
+        );
+        let input = Input::new(stacks, vec![Move::new(2, 11, 10)]);
+
+        assert_eq!(input, generate(&input.to_string()));
+        assert_eq!(
+            "          [32]               [72]                [112]    
+     [21] [31]          [61] [71]          [101] [111]    
+[10] [20] [30]     [50] [60] [70]     [90] [100] [110]    
+ 1    2    3    4   5    6    7    8   9    10    11   12 ",
+            render(&input.stacks)
+        );
+    }
+
+    #[test]
+    fn test15() {
+        let stacks = Stacks::from(vec![vec!['A'], vec![], vec!['B']]);
+
+        assert_eq!("[A]     [B]\n 1   2   3 ", render(&stacks));
+        assert_eq!(stacks, generate(&format!("{}\n\n", render(&stacks))).stacks);
+    }
+
+    #[test]
+    fn test16() {
+        let input = generate(EXAMPLE);
+        let mut replay = Replay::new(&input, CrateMover9000);
+
+        assert_eq!("NDP", replay.top());
+        assert_eq!(Ok("DCP".to_string()), replay.top_at(1));
+        assert_eq!(Ok("CMZ".to_string()), replay.top_at(4));
+        assert_eq!(Ok(false), replay.step_forward());
+        assert_eq!(4, replay.position());
+        assert_eq!(Ok("CZ".to_string()), replay.top_at(2));
+        assert!(replay.step_backward());
+        assert!(replay.step_backward());
+        assert!(!replay.step_backward());
+        assert_eq!(&input.stacks, replay.stacks());
+    }
+
+    #[test]
+    fn test17() {
+        let input = generate(EXAMPLE);
+        let mut replay = Replay::new(&input, CappedCrane::new(2).unwrap());
+        let mut expected = vec![input.stacks.clone()];
+        simulate_steps(&input, &CappedCrane::new(2).unwrap(), |_, stacks| {
+            expected.push(stacks.clone())
+        })
+        .unwrap();
+
+        for position in [3, 1, 4, 0, 2, 4] {
+            replay.jump(position).unwrap();
+            assert_eq!(&expected[position], replay.stacks());
+        }
+    }
+
+    #[test]
+    fn test18() {
+        let input = with_moves(vec![Move::new(1, 2, 1), Move::new(5, 1, 2)]);
+        let mut replay = Replay::new(&input, CrateMover9001);
+        let actual = replay.jump(2).unwrap_err();
+
+        assert_eq!(1, actual.index);
+        assert_eq!(1, replay.position());
+    }
+
+    fn apply_all(stacks: &Stacks, moves: &[Move], crane: &impl Crane) -> String {
+        let input = Input::new(stacks.clone(), moves.to_vec());
+        top_of_stacks(&simulate(&input, crane).unwrap())
+    }
+
+    #[test]
+    fn test19() {
+        let input = generate(EXAMPLE);
+
+        assert_eq!(Some(vec![]), plan(&input.stacks, "NDP", &CrateMover9000, 0));
+        assert_eq!(
+            Some(vec![Move::new(1, 2, 1)]),
+            plan(&input.stacks, "DCP", &CrateMover9000, 3)
+        );
+        assert_eq!(None, plan(&input.stacks, "DCP", &CrateMover9000, 0));
+        assert_eq!(None, plan(&input.stacks, "ZZZ", &CrateMover9001, 3));
+    }
+
+    fn check_plan(stacks: &Stacks, target: &str, crane: &impl Crane) {
+        let moves = plan(stacks, target, crane, 4).unwrap();
+        assert!(moves.len() <= 4);
+        assert_eq!(target, apply_all(stacks, &moves, crane));
+        if !moves.is_empty() {
+            assert_eq!(None, plan(stacks, target, crane, moves.len() - 1));
+        }
+    }
+
+    #[test]
+    fn test20() {
+        let input = generate(EXAMPLE);
+
+        check_plan(&input.stacks, "CMZ", &CrateMover9000);
+        check_plan(&input.stacks, "MCD", &CrateMover9001);
+    }
+
+    #[test]
+    fn test21() {
+        let stacks = Stacks::from(vec![vec!['A', 'B'], vec!['C']]);
+
+        assert_eq!(
+            Some(vec![Move::new(2, 1, 2)]),
+            plan(&stacks, "B", &CrateMover9001, 2)
+        );
+        assert_eq!(None, plan(&stacks, "B", &CrateMover9000, 4));
+        check_plan(&stacks, "AB", &CrateMover9000);
+    }
+
+    #[test]
+    fn test22() {
+        assert!(CappedCrane::new(0).is_none());
+
+        let input = with_moves(vec![Move::new(3, 2, 1)]);
+        let crane = CappedCrane::new(2).unwrap();
+        let expected = Stacks::from(vec![vec!['Z', 'N', 'C', 'D', 'M'], vec![], vec!['P']]);
+
+        assert_eq!(Ok(expected), simulate(&input, &crane))
+    }
 }
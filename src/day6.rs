@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    io::{self, BufReader, Read},
+};
 
 use aoc_runner_derive::aoc;
 
-fn solve_generic(input: &str, window: usize) -> u32 {
+fn solve_generic_hashmap(input: &str, window: usize) -> u32 {
     let mut out = window as u32;
     let mut counts = HashMap::<char, u32>::default();
 
@@ -43,23 +48,196 @@ fn solve_generic(input: &str, window: usize) -> u32 {
     out
 }
 
+/// Watches a stream one byte at a time for runs of `window` distinct bytes.
+/// Keeps a count of each byte in the window along with how many bytes appear
+/// more than once, so every step is constant time whatever the window size.
+pub struct Detector {
+    window: usize,
+    recent: VecDeque<u8>,
+    counts: [u32; 256],
+    duplicated: usize,
+    position: usize,
+}
+
+impl Detector {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: [0; 256],
+            duplicated: 0,
+            position: 0,
+        }
+    }
+
+    /// Adds the next byte, returning the number of bytes seen so far if the
+    /// last `window` of them are all distinct.
+    pub fn push(&mut self, b: u8) -> Option<usize> {
+        self.position += 1;
+        self.recent.push_back(b);
+        self.counts[b as usize] += 1;
+        if self.counts[b as usize] == 2 {
+            self.duplicated += 1;
+        }
+        if self.recent.len() > self.window {
+            let left = self.recent.pop_front().unwrap() as usize;
+            self.counts[left] -= 1;
+            if self.counts[left] == 1 {
+                self.duplicated -= 1;
+            }
+        }
+        if self.recent.len() == self.window && self.duplicated == 0 {
+            Some(self.position)
+        } else {
+            None
+        }
+    }
+}
+
+/// Every position in `bytes` where the last `window` bytes are distinct.
+pub fn markers(bytes: impl IntoIterator<Item = u8>, window: usize) -> impl Iterator<Item = usize> {
+    let mut detector = Detector::new(window);
+    bytes.into_iter().filter_map(move |b| detector.push(b))
+}
+
+/// Like `markers`, but reading from `reader` until it ends or fails.
+pub fn read_markers(reader: impl Read, window: usize) -> impl Iterator<Item = io::Result<usize>> {
+    let mut detector = Detector::new(window);
+    let mut bytes = BufReader::new(reader).bytes();
+    std::iter::from_fn(move || loop {
+        match bytes.next()? {
+            Ok(b) => {
+                if let Some(position) = detector.push(b) {
+                    return Some(Ok(position));
+                }
+            }
+            Err(e) => return Some(Err(e)),
+        }
+    })
+}
+
+/// Finds the end of the first run of `window` distinct bytes.
+pub fn find_marker(input: &[u8], window: usize) -> Option<usize> {
+    if window == 0 {
+        return Some(0);
+    }
+    markers(input.iter().copied(), window).next()
+}
+
+const PACKET_WINDOW: usize = 4;
+const MESSAGE_WINDOW: usize = 14;
+
+#[derive(PartialEq, Debug)]
+pub enum DecodeError {
+    NoMarker { window: usize },
+    NoMessageMarker { packet: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NoMarker { window } => {
+                write!(f, "no run of {} distinct characters in the stream", window)
+            }
+            DecodeError::NoMessageMarker { packet } => write!(
+                f,
+                "no start-of-message marker after the start-of-packet marker at {}",
+                packet
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// A start-of-packet marker, the start-of-message marker after it, and the
+/// payload that follows up to the next frame. Positions are byte offsets in
+/// the stream.
+#[derive(PartialEq, Debug)]
+pub struct Frame<'a> {
+    /// Where the start-of-packet marker begins.
+    pub start: usize,
+    /// Just past the start-of-message marker, where the payload begins.
+    pub message: usize,
+    /// Just past the end of the payload.
+    pub end: usize,
+    pub payload: &'a [u8],
+}
+
+/// Splits a stream into frames. A frame only starts at a start-of-packet
+/// marker that is followed by a start-of-message marker; any other bytes,
+/// including markers without a message after them, are payload.
+pub fn decode(input: &[u8]) -> Result<Vec<Frame<'_>>, DecodeError> {
+    let mut bounds = Vec::<(usize, usize)>::new();
+    let mut offset = 0;
+    while let Some(packet) = find_marker(&input[offset..], PACKET_WINDOW) {
+        let packet = offset + packet;
+        let Some(message) = find_marker(&input[packet..], MESSAGE_WINDOW) else {
+            if bounds.is_empty() {
+                return Err(DecodeError::NoMessageMarker { packet });
+            }
+            break;
+        };
+        bounds.push((packet - PACKET_WINDOW, packet + message));
+        offset = packet + message;
+    }
+    if bounds.is_empty() {
+        return Err(DecodeError::NoMarker {
+            window: PACKET_WINDOW,
+        });
+    }
+    let ends = bounds
+        .iter()
+        .skip(1)
+        .map(|&(start, _)| start)
+        .chain([input.len()])
+        .collect::<Vec<_>>();
+    Ok(bounds
+        .into_iter()
+        .zip(ends)
+        .map(|((start, message), end)| Frame {
+            start,
+            message,
+            end,
+            payload: &input[message..end],
+        })
+        .collect())
+}
+
+fn solve_generic(input: &str, window: usize) -> Result<u32, DecodeError> {
+    find_marker(input.as_bytes(), window)
+        .map(|position| position as u32)
+        .ok_or(DecodeError::NoMarker { window })
+}
+
 #[aoc(day6, part1)]
-pub fn solve_part1(input: &str) -> u32 {
-    solve_generic(input, 4)
+pub fn solve_part1(input: &str) -> Result<u32, DecodeError> {
+    solve_generic(input, PACKET_WINDOW)
 }
 
 #[aoc(day6, part2)]
-fn solve_part2(input: &str) -> u32 {
-    solve_generic(input, 14)
+fn solve_part2(input: &str) -> Result<u32, DecodeError> {
+    solve_generic(input, MESSAGE_WINDOW)
+}
+
+#[aoc(day6, part1, hashmap)]
+pub fn solve_part1_hashmap(input: &str) -> u32 {
+    solve_generic_hashmap(input, 4)
+}
+
+#[aoc(day6, part2, hashmap)]
+fn solve_part2_hashmap(input: &str) -> u32 {
+    solve_generic_hashmap(input, 14)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{timed, XorShift};
 
     #[test]
     fn test1() {
-        let expected = 7;
+        let expected = Ok(7);
         let actual = solve_part1("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
 
         assert_eq!(expected, actual)
@@ -67,7 +245,7 @@ mod tests {
 
     #[test]
     fn test2() {
-        let expected = 5;
+        let expected = Ok(5);
         let actual = solve_part1("bvwbjplbgvbhsrlpgdmjqwftvncz");
 
         assert_eq!(expected, actual)
@@ -75,7 +253,7 @@ mod tests {
 
     #[test]
     fn test3() {
-        let expected = 6;
+        let expected = Ok(6);
         let actual = solve_part1("nppdvjthqldpwncqszvftbrmjlhg");
 
         assert_eq!(expected, actual)
@@ -83,7 +261,7 @@ mod tests {
 
     #[test]
     fn test4() {
-        let expected = 10;
+        let expected = Ok(10);
         let actual = solve_part1("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg");
 
         assert_eq!(expected, actual)
@@ -91,7 +269,7 @@ mod tests {
 
     #[test]
     fn test5() {
-        let expected = 11;
+        let expected = Ok(11);
         let actual = solve_part1("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw");
 
         assert_eq!(expected, actual)
@@ -99,7 +277,7 @@ mod tests {
 
     #[test]
     fn test6() {
-        let expected = 19;
+        let expected = Ok(19);
         let actual = solve_part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
 
         assert_eq!(expected, actual)
@@ -107,7 +285,7 @@ mod tests {
 
     #[test]
     fn test7() {
-        let expected = 23;
+        let expected = Ok(23);
         let actual = solve_part2("bvwbjplbgvbhsrlpgdmjqwftvncz");
 
         assert_eq!(expected, actual)
@@ -115,7 +293,7 @@ mod tests {
 
     #[test]
     fn test8() {
-        let expected = 23;
+        let expected = Ok(23);
         let actual = solve_part2("nppdvjthqldpwncqszvftbrmjlhg");
 
         assert_eq!(expected, actual)
@@ -123,7 +301,7 @@ mod tests {
 
     #[test]
     fn test9() {
-        let expected = 29;
+        let expected = Ok(29);
         let actual = solve_part2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg");
 
         assert_eq!(expected, actual)
@@ -131,9 +309,150 @@ mod tests {
 
     #[test]
     fn test10() {
-        let expected = 26;
+        let expected = Ok(26);
         let actual = solve_part2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw");
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test11() {
+        for input in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "aaaaaaaaaaaaaaaaaaaa",
+            "abc",
+            "",
+        ] {
+            for window in [1, 2, 4, 14] {
+                match solve_generic(input, window) {
+                    Ok(actual) => assert_eq!(
+                        solve_generic_hashmap(input, window),
+                        actual,
+                        "{} with window {}",
+                        input,
+                        window
+                    ),
+                    Err(e) => assert_eq!(DecodeError::NoMarker { window }, e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test12() {
+        assert_eq!(Some(1), find_marker(b"aab", 1));
+        assert_eq!(Some(3), find_marker(b"aab", 2));
+        assert_eq!(None, find_marker(b"aab", 3));
+        assert_eq!(None, find_marker(b"ab", 3));
+    }
+
+    #[test]
+    fn test13() {
+        let expected = (7..=30).collect::<Vec<_>>();
+        let actual = markers("mjqjpqmgbljsphdztnvjfqwrcgsmlb".bytes(), 4).collect::<Vec<_>>();
+
+        assert_eq!(expected, actual)
+    }
+
+    #[test]
+    fn test14() {
+        let reader = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes();
+        let expected = vec![19, 25, 26, 27, 28, 29, 30];
+        let actual = read_markers(reader, 14)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(expected, actual)
+    }
+
+    struct Failing;
+
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disconnected"))
+        }
+    }
+
+    #[test]
+    fn test15() {
+        let mut markers = read_markers("abcd".as_bytes().chain(Failing), 4);
+
+        assert_eq!(4, markers.next().unwrap().unwrap());
+        assert!(markers.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test16() {
+        let input = b"abcdefghijklmnopqrHELLOaaaabcdefghijklmnopqrWORLD";
+        let expected = Ok(vec![
+            Frame {
+                start: 0,
+                message: 18,
+                end: 26,
+                payload: b"HELLOaaa",
+            },
+            Frame {
+                start: 26,
+                message: 44,
+                end: 49,
+                payload: b"WORLD",
+            },
+        ]);
+
+        assert_eq!(expected, decode(input))
+    }
+
+    #[test]
+    fn test17() {
+        let expected = Ok(vec![Frame {
+            start: 3,
+            message: 25,
+            end: 30,
+            payload: b"gsmlb",
+        }]);
+
+        assert_eq!(expected, decode(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb"))
+    }
+
+    #[test]
+    fn test18() {
+        assert_eq!(Err(DecodeError::NoMarker { window: 4 }), decode(b"aaaa"));
+        assert_eq!(
+            Err(DecodeError::NoMessageMarker { packet: 4 }),
+            decode(b"abcdaaaa")
+        );
+        assert_eq!(
+            Err(DecodeError::NoMarker { window: 14 }),
+            solve_part2("abcd")
+        );
+    }
+
+    fn synthetic_stream(len: usize, alphabet: usize, window: usize) -> String {
+        let mut rng = XorShift::new(0x2545_f491_4f6c_dd1d);
+        let mut out = (0..len)
+            .map(|_| (b'a' + rng.below(alphabet) as u8) as char)
+            .collect::<String>();
+        out.extend(('A'..='Z').take(window));
+        out
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with --release -- --ignored"]
+    fn bench_find_marker() {
+        for (alphabet, window) in [(3, PACKET_WINDOW), (13, MESSAGE_WINDOW)] {
+            let input = synthetic_stream(8_000_000, alphabet, window);
+
+            let (expected, hashmap) = timed(|| solve_generic_hashmap(&input, window));
+            let (actual, counts) = timed(|| solve_generic(&input, window));
+            assert_eq!(Ok(expected), actual);
+            assert!(
+                counts < hashmap,
+                "window {}: counts {:?}, hashmap {:?}",
+                window,
+                counts,
+                hashmap
+            );
+        }
+    }
 }
@@ -1,13 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, error::Error, fmt, fs, io, path::Path};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
-#[derive(Default)]
-struct TreeNode {
-    size: u32,
-    children: HashMap<String, TreeNode>,
-}
-
 #[derive(PartialEq, Debug)]
 pub enum InputLine {
     Command(Command),
@@ -23,134 +17,737 @@ pub enum Command {
 #[derive(PartialEq, Debug)]
 pub enum LsOutput {
     Directory(String),
-    File(u32, String),
+    File(u64, String),
+}
+
+/// A transcript line that isn't a command or a listing entry. Lines are
+/// numbered from 1.
+#[derive(PartialEq, Debug)]
+pub enum ParseError {
+    UnknownCommand { line: usize, command: String },
+    MissingDirectory { line: usize },
+    InvalidSize { line: usize, size: String },
+    InvalidEntry { line: usize },
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command {:?}", line, command)
+            }
+            ParseError::MissingDirectory { line } => {
+                write!(f, "line {}: cd needs a directory", line)
+            }
+            ParseError::InvalidSize { line, size } => {
+                write!(f, "line {}: invalid size {:?}", line, size)
+            }
+            ParseError::InvalidEntry { line } => write!(
+                f,
+                "line {}: expected \"<size> <name>\" or \"dir <name>\"",
+                line
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
 #[aoc_generator(day7)]
-pub fn generate(input: &str) -> Vec<InputLine> {
+pub fn generate(input: &str) -> Result<Vec<InputLine>, ParseError> {
+    // Names run to the end of the line, so they may contain spaces.
     input
         .lines()
-        .map(|l| {
-            let mut split = l.split(' ').peekable();
-            match split.peek().unwrap() {
-                &"$" => {
-                    split.next().unwrap();
-                    match split.peek().unwrap() {
-                        &"cd" => {
-                            split.next().unwrap();
-                            InputLine::Command(Command::CD(split.peek().unwrap().to_string()))
-                        }
-                        &"ls" => InputLine::Command(Command::LS),
-                        _ => unreachable!(),
-                    }
-                }
-                &"dir" => {
-                    split.next().unwrap();
-                    InputLine::LsOutput(LsOutput::Directory(split.peek().unwrap().to_string()))
-                }
-                _ => {
-                    let size = split.next().unwrap().parse::<u32>().unwrap();
-                    InputLine::LsOutput(LsOutput::File(size, split.next().unwrap().to_string()))
+        .enumerate()
+        .map(|(idx, l)| {
+            let line = idx + 1;
+            if let Some(command) = l.strip_prefix('$') {
+                let command = command.strip_prefix(' ').unwrap_or(command);
+                match command.split_once(' ') {
+                    Some(("cd", "")) => Err(ParseError::MissingDirectory { line }),
+                    Some(("cd", path)) => Ok(InputLine::Command(Command::CD(path.to_string()))),
+                    None if command == "cd" => Err(ParseError::MissingDirectory { line }),
+                    None if command == "ls" => Ok(InputLine::Command(Command::LS)),
+                    _ => Err(ParseError::UnknownCommand {
+                        line,
+                        command: command.to_string(),
+                    }),
                 }
+            } else if let Some(name) = l.strip_prefix("dir ") {
+                Ok(InputLine::LsOutput(LsOutput::Directory(name.to_string())))
+            } else {
+                let (size, name) = l.split_once(' ').ok_or(ParseError::InvalidEntry { line })?;
+                let size = size.parse::<u64>().map_err(|_| ParseError::InvalidSize {
+                    line,
+                    size: size.to_string(),
+                })?;
+                Ok(InputLine::LsOutput(LsOutput::File(size, name.to_string())))
             }
         })
         .collect()
 }
 
-fn dfs_1(node: &TreeNode) -> (u32, u32) {
-    if node.children.is_empty() {
-        return (node.size, 0);
+pub type NodeId = usize;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum NodeKind {
+    File(u64),
+    Directory(BTreeMap<String, NodeId>),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub kind: NodeKind,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum VfsError {
+    NotADirectory(String),
+    NotAFile(String),
+    SizeMismatch { path: String, old: u64, new: u64 },
+    InvalidName(String),
+}
+
+impl fmt::Display for VfsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VfsError::NotADirectory(path) => write!(f, "{} is not a directory", path),
+            VfsError::NotAFile(path) => write!(f, "{} is not a file", path),
+            VfsError::SizeMismatch { path, old, new } => {
+                write!(f, "{} has size {}, not {}", path, old, new)
+            }
+            VfsError::InvalidName(name) => write!(f, "{:?} is not a valid name", name),
+        }
     }
-    let mut child_size_under_threshold = 0;
-    let mut size = 0;
-    for child in node.children.values() {
-        let (child_size, child_children_under_threshold) = dfs_1(child);
-        child_size_under_threshold += child_children_under_threshold;
-        size += child_size;
+}
+
+impl Error for VfsError {}
+
+/// A filesystem tree. Nodes live in one `Vec` and refer to each other by
+/// index, and are always added after their parent.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Vfs {
+    nodes: Vec<Node>,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new()
     }
-    (
-        size,
-        if size <= 100_000 {
-            child_size_under_threshold + size
-        } else {
-            child_size_under_threshold
-        },
-    )
-}
-
-fn dfs_2(current_free_size: u32, node: &TreeNode) -> (u32, u32) {
-    if node.children.is_empty() {
-        return (node.size, u32::MAX);
-    }
-    let mut size = 0;
-    let mut smallest_removal_size = u32::MAX;
-    for child in node.children.values() {
-        let (child_size, smallest_child_removal_size) = dfs_2(current_free_size, child);
-        size += child_size;
-        smallest_removal_size = std::cmp::min(smallest_removal_size, smallest_child_removal_size);
-    }
-    (
-        size,
-        if current_free_size + size >= 30000000 {
-            std::cmp::min(smallest_removal_size, size)
-        } else {
-            smallest_removal_size
-        },
-    )
-}
-
-fn parse_subtree(input_stream: &mut dyn Iterator<Item = &InputLine>, root: &mut TreeNode) {
-    loop {
-        let next = input_stream.next();
-        if next.is_none() {
-            return;
-        }
-        match next.unwrap() {
-            InputLine::Command(command) => match command {
-                Command::CD(path) => match path.as_str() {
-                    ".." => return,
-                    _ => {
-                        let subtree = root.children.get_mut(path.as_str()).unwrap();
-                        parse_subtree(input_stream, subtree);
-                    }
-                },
-                Command::LS => {}
+}
+
+impl Vfs {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: String::new(),
+                parent: None,
+                kind: NodeKind::Directory(BTreeMap::new()),
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id].kind, NodeKind::Directory(_))
+    }
+
+    pub fn child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[parent].kind {
+            NodeKind::Directory(children) => children.get(name).copied(),
+            NodeKind::File(_) => None,
+        }
+    }
+
+    /// Adds a node. Names that `lookup` or `cd` would read as something
+    /// else, or that would point outside the tree once written to disk, are
+    /// rejected.
+    fn add(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> Result<NodeId, VfsError> {
+        if matches!(name, "" | "." | "..") || name.contains('/') {
+            return Err(VfsError::InvalidName(name.to_string()));
+        }
+        let id = self.nodes.len();
+        match &mut self.nodes[parent].kind {
+            NodeKind::Directory(children) => {
+                children.insert(name.to_string(), id);
+            }
+            NodeKind::File(_) => return Err(VfsError::NotADirectory(self.path(parent))),
+        }
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            kind,
+        });
+        Ok(id)
+    }
+
+    /// Creates a directory, or returns the existing one of that name.
+    pub fn mkdir(&mut self, parent: NodeId, name: &str) -> Result<NodeId, VfsError> {
+        match self.child(parent, name) {
+            Some(id) if self.is_dir(id) => Ok(id),
+            Some(id) => Err(VfsError::NotADirectory(self.path(id))),
+            None => self.add(parent, name, NodeKind::Directory(BTreeMap::new())),
+        }
+    }
+
+    /// Creates a file, or returns the existing one of that name and size.
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Result<NodeId, VfsError> {
+        match self.child(parent, name) {
+            Some(id) => match self.nodes[id].kind {
+                NodeKind::File(old) if old == size => Ok(id),
+                NodeKind::File(old) => Err(VfsError::SizeMismatch {
+                    path: self.path(id),
+                    old,
+                    new: size,
+                }),
+                NodeKind::Directory(_) => Err(VfsError::NotAFile(self.path(id))),
             },
-            InputLine::LsOutput(ls_output) => match ls_output {
-                LsOutput::Directory(name) => {
-                    let new_node = TreeNode::default();
-                    root.children.insert(name.clone(), new_node);
+            None => self.add(parent, name, NodeKind::File(size)),
+        }
+    }
+
+    /// Finds a node by its absolute path, like `/a/e`.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(Self::ROOT, |id, part| self.child(id, part))
+    }
+
+    pub fn path(&self, id: NodeId) -> String {
+        let mut parts = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.nodes[current].parent {
+            parts.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+        if parts.is_empty() {
+            return "/".to_string();
+        }
+        parts
+            .iter()
+            .rev()
+            .map(|part| format!("/{}", part))
+            .collect()
+    }
+
+    /// The contents of a directory, ordered by name.
+    pub fn list(&self, id: NodeId) -> Option<Vec<NodeId>> {
+        match &self.nodes[id].kind {
+            NodeKind::Directory(children) => Some(children.values().copied().collect()),
+            NodeKind::File(_) => None,
+        }
+    }
+
+    /// The total size of every node, indexed by id.
+    pub fn sizes(&self) -> Vec<u64> {
+        let mut sizes = self
+            .nodes
+            .iter()
+            .map(|node| match node.kind {
+                NodeKind::File(size) => size,
+                NodeKind::Directory(_) => 0,
+            })
+            .collect::<Vec<_>>();
+        for id in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[id].parent.unwrap();
+            sizes[parent] += sizes[id];
+        }
+        sizes
+    }
+
+    pub fn size(&self, id: NodeId) -> u64 {
+        self.walk(id, None)
+            .into_iter()
+            .map(|(id, _)| match self.nodes[id].kind {
+                NodeKind::File(size) => size,
+                NodeKind::Directory(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Every node under `id`, itself included, in depth-first order and with
+    /// its depth relative to `id`. Stops descending below `max_depth`.
+    pub fn walk(&self, id: NodeId, max_depth: Option<usize>) -> Vec<(NodeId, usize)> {
+        let mut out = Vec::new();
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            out.push((id, depth));
+            if max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            if let NodeKind::Directory(children) = &self.nodes[id].kind {
+                stack.extend(children.values().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+        out
+    }
+
+    /// Every node for which `predicate` holds, given the node and its total
+    /// size.
+    pub fn find(&self, predicate: impl Fn(&Node, u64) -> bool) -> Vec<NodeId> {
+        let sizes = self.sizes();
+        self.walk(Self::ROOT, None)
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|&id| predicate(&self.nodes[id], sizes[id]))
+            .collect()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Vec<NodeId> {
+        self.find(|node, _| node.name == name)
+    }
+
+    /// Follows a `cd` argument from `cwd`. Directories along the way that
+    /// haven't been listed yet are created.
+    pub fn resolve(&mut self, cwd: NodeId, path: &str) -> Result<NodeId, VfsError> {
+        let start = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            cwd
+        };
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(start, |id, part| match part {
+                "." => Ok(id),
+                ".." => Ok(self.nodes[id].parent.unwrap_or(Self::ROOT)),
+                name => self.mkdir(id, name),
+            })
+    }
+
+    /// Replays a transcript. Listing a directory again is fine as long as
+    /// the listings agree.
+    pub fn from_transcript(input: &[InputLine]) -> Result<Self, TranscriptError> {
+        let mut vfs = Self::new();
+        let mut cwd = Self::ROOT;
+        for (idx, line) in input.iter().enumerate() {
+            let result = match line {
+                InputLine::Command(Command::CD(path)) => vfs.resolve(cwd, path).map(|id| cwd = id),
+                InputLine::Command(Command::LS) => Ok(()),
+                InputLine::LsOutput(LsOutput::Directory(name)) => vfs.mkdir(cwd, name).map(|_| ()),
+                InputLine::LsOutput(LsOutput::File(size, name)) => {
+                    vfs.add_file(cwd, name, *size).map(|_| ())
                 }
-                LsOutput::File(size, name) => {
-                    let mut new_node = TreeNode::default();
-                    new_node.size = *size;
-                    root.children.insert(name.clone(), new_node);
+            };
+            result.map_err(|error| TranscriptError {
+                line: idx + 1,
+                error,
+            })?;
+        }
+        Ok(vfs)
+    }
+
+    /// Reads a real directory tree. Anything that isn't a file or directory,
+    /// symlinks included, is left out.
+    pub fn from_dir(path: &Path) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut vfs = Self::new();
+        let mut stack = vec![(path.to_path_buf(), Self::ROOT)];
+        while let Some((dir, id)) = stack.pop() {
+            let mut entries = fs::read_dir(&dir)?.collect::<io::Result<Vec<_>>>()?;
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                let path = entry.path();
+                let name = entry
+                    .file_name()
+                    .into_string()
+                    .map_err(|_| invalid(format!("{} is not valid UTF-8", path.display())))?;
+                if name.contains('\n') {
+                    return Err(invalid(format!(
+                        "{} has a newline in its name",
+                        path.display()
+                    )));
                 }
-            },
+                let file_type = entry.file_type()?;
+                let added = if file_type.is_dir() {
+                    vfs.mkdir(id, &name).map(|child| stack.push((path, child)))
+                } else if file_type.is_file() {
+                    vfs.add_file(id, &name, entry.metadata()?.len()).map(|_| ())
+                } else {
+                    Ok(())
+                };
+                added.map_err(|error| invalid(error.to_string()))?;
+            }
+        }
+        Ok(vfs)
+    }
+
+    /// Recreates the tree under `dir`, with sparse files of the right sizes.
+    pub fn write_to(&self, dir: &Path) -> io::Result<()> {
+        for (id, _) in self.walk(Self::ROOT, None) {
+            let path = dir.join(self.path(id).trim_start_matches('/'));
+            match self.nodes[id].kind {
+                NodeKind::Directory(_) => fs::create_dir_all(&path)?,
+                NodeKind::File(size) => fs::File::create(&path)?.set_len(size)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A transcript line that doesn't agree with what came before it.
+#[derive(PartialEq, Debug)]
+pub struct TranscriptError {
+    pub line: usize,
+    pub error: VfsError,
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for TranscriptError {}
+
+/// The disk the transcript was taken on, and what counts as a small
+/// directory or enough free space.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct DiskPolicy {
+    pub capacity: u64,
+    pub required_free: u64,
+    pub small_threshold: u64,
+}
+
+impl Default for DiskPolicy {
+    fn default() -> Self {
+        Self {
+            capacity: 70_000_000,
+            required_free: 30_000_000,
+            small_threshold: 100_000,
+        }
+    }
+}
+
+/// The total size of all directories no larger than the small threshold.
+pub fn small_directories_total(vfs: &Vfs, policy: &DiskPolicy) -> u64 {
+    let sizes = vfs.sizes();
+    (0..vfs.node_count())
+        .filter(|&id| vfs.is_dir(id) && sizes[id] <= policy.small_threshold)
+        .map(|id| sizes[id])
+        .sum()
+}
+
+/// The size of the smallest directory that frees up enough space. There is
+/// always one unless more space is required than the disk has.
+pub fn smallest_cleanup(vfs: &Vfs, policy: &DiskPolicy) -> Option<u64> {
+    let sizes = vfs.sizes();
+    let free = policy.capacity.saturating_sub(sizes[Vfs::ROOT]);
+    (0..vfs.node_count())
+        .filter(|&id| vfs.is_dir(id) && free + sizes[id] >= policy.required_free)
+        .map(|id| sizes[id])
+        .min()
+}
+
+/// How many partial totals the planner keeps per node before it starts
+/// dropping totals that are within a small ratio of a larger one.
+const MAX_STATES: usize = 1 << 10;
+
+/// A set of deleted nodes, shared between the totals that include it.
+enum Pick {
+    Node(NodeId),
+    Both(usize, usize),
+}
+
+/// Reachable deletion totals, ascending. Every total but the last is short of
+/// the target; the last may meet it.
+type States<T> = Vec<(u64, T)>;
+
+struct Planner {
+    need: u64,
+    ratio: f64,
+    picks: Vec<Pick>,
+    exact: bool,
+}
+
+impl Planner {
+    fn new(need: u64) -> Self {
+        Self {
+            need,
+            ratio: (need.max(2) as f64).powf(2.0 / MAX_STATES as f64),
+            picks: Vec::new(),
+            exact: true,
+        }
+    }
+
+    fn pick(&mut self, pick: Pick) -> Option<usize> {
+        self.picks.push(pick);
+        Some(self.picks.len() - 1)
+    }
+
+    fn join(&mut self, left: Option<usize>, right: Option<usize>) -> Option<usize> {
+        match (left, right) {
+            (None, pick) | (pick, None) => pick,
+            (Some(left), Some(right)) => self.pick(Pick::Both(left, right)),
+        }
+    }
+
+    /// Sorts, keeps the first pick for each total and drops everything past
+    /// the smallest total that meets the target. Past `MAX_STATES` it also
+    /// drops totals just below a kept one, which makes the plan approximate.
+    fn settle<T>(&mut self, mut states: States<T>) -> States<T> {
+        states.sort_by_key(|&(total, _)| total);
+        states.dedup_by_key(|&mut (total, _)| total);
+        let enough = states.partition_point(|&(total, _)| total < self.need);
+        states.truncate(enough + 1);
+        if states.len() <= MAX_STATES {
+            return states;
+        }
+        self.exact = false;
+        let mut kept = states.pop().into_iter().collect::<Vec<_>>();
+        let mut bound = f64::INFINITY;
+        if kept[0].0 < self.need {
+            bound = kept[0].0 as f64;
+        }
+        while let Some(state) = states.pop() {
+            if (state.0 as f64) * self.ratio < bound {
+                bound = state.0 as f64;
+                kept.push(state);
+            }
+        }
+        kept.reverse();
+        kept
+    }
+
+    /// Every total reachable by deleting from both sides.
+    fn merge(
+        &mut self,
+        left: &States<Option<usize>>,
+        right: &States<Option<usize>>,
+    ) -> States<Option<usize>> {
+        let mut states = Vec::with_capacity(left.len() * right.len());
+        for &(a, left) in left {
+            for &(b, right) in right {
+                states.push((a.saturating_add(b), (left, right)));
+            }
+        }
+        self.settle(states)
+            .into_iter()
+            .map(|(total, (left, right))| (total, self.join(left, right)))
+            .collect()
+    }
+
+    fn nodes(&self, pick: Option<usize>) -> Vec<NodeId> {
+        let mut out = Vec::new();
+        let mut stack = pick.into_iter().collect::<Vec<_>>();
+        while let Some(pick) = stack.pop() {
+            match self.picks[pick] {
+                Pick::Node(id) => out.push(id),
+                Pick::Both(left, right) => stack.extend([left, right]),
+            }
+        }
+        out
+    }
+}
+
+/// Nodes to delete to free up enough space.
+#[derive(PartialEq, Debug)]
+pub struct Cleanup {
+    pub freed: u64,
+    pub paths: Vec<String>,
+    /// Whether `freed` is known to be the least possible.
+    pub exact: bool,
+}
+
+/// Picks files and directories, none inside another, that free up enough
+/// space while deleting as little as possible. Small trees are solved
+/// exactly; on large ones the result may delete slightly more than needed.
+/// Returns `None` if even deleting everything isn't enough.
+pub fn plan_cleanup(vfs: &Vfs, policy: &DiskPolicy) -> Option<Cleanup> {
+    let sizes = vfs.sizes();
+    let free = policy.capacity.saturating_sub(sizes[Vfs::ROOT]);
+    let mut planner = Planner::new(policy.required_free.saturating_sub(free));
+    let mut states: Vec<Option<States<Option<usize>>>> = vec![None; vfs.node_count()];
+    // Children always come after their parent.
+    for id in (0..vfs.node_count()).rev() {
+        let mut own = vec![(0, None)];
+        for child in vfs.list(id).unwrap_or_default() {
+            own = planner.merge(&own, &states[child].take().unwrap());
+        }
+        // Deleting the whole node beats deleting all of its contents, but
+        // deleting an empty one frees nothing and would only clutter the plan.
+        let mut all = Vec::new();
+        if sizes[id] > 0 {
+            all.push((sizes[id], planner.pick(Pick::Node(id))));
+        }
+        all.append(&mut own);
+        states[id] = Some(planner.settle(all));
+    }
+
+    let &(freed, pick) = states[Vfs::ROOT].as_ref().unwrap().last().unwrap();
+    if freed < planner.need {
+        return None;
+    }
+    let mut paths = planner
+        .nodes(pick)
+        .into_iter()
+        .map(|id| vfs.path(id))
+        .collect::<Vec<_>>();
+    paths.sort();
+    Some(Cleanup {
+        freed,
+        paths,
+        exact: planner.exact,
+    })
+}
+
+/// Draws the tree like `tree --du`, with every node's total size.
+pub fn render_tree(vfs: &Vfs) -> String {
+    let sizes = vfs.sizes();
+    let mut lines = Vec::new();
+    // Whether each ancestor below the root is the last of its siblings.
+    let mut last = Vec::new();
+    for (id, depth) in vfs.walk(Vfs::ROOT, None) {
+        let node = vfs.node(id);
+        let name = match (&node.kind, node.parent) {
+            (_, None) => "/".to_string(),
+            (NodeKind::Directory(_), _) => format!("{}/", node.name),
+            (NodeKind::File(_), _) => node.name.clone(),
+        };
+        let mut prefix = String::new();
+        if let Some(parent) = node.parent {
+            last.truncate(depth - 1);
+            for &done in &last {
+                prefix.push_str(if done { "    " } else { "│   " });
+            }
+            let is_last = matches!(
+                &vfs.node(parent).kind,
+                NodeKind::Directory(children) if children.values().next_back() == Some(&id)
+            );
+            prefix.push_str(if is_last { "└── " } else { "├── " });
+            last.push(is_last);
+        }
+        lines.push(format!("{}[{}] {}", prefix, sizes[id], name));
+    }
+    lines.join("\n")
+}
+
+/// Directory sizes, and file sizes too if `all` is set, largest first like
+/// `du -a | sort -rn`.
+pub fn du(vfs: &Vfs, all: bool) -> Vec<(u64, String)> {
+    let sizes = vfs.sizes();
+    let mut entries = (0..vfs.node_count())
+        .filter(|&id| all || vfs.is_dir(id))
+        .map(|id| (sizes[id], vfs.path(id)))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    entries
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A transcript that lists every directory once, depth first.
+pub fn to_transcript(vfs: &Vfs) -> String {
+    let mut lines = vec!["$ cd /".to_string()];
+    let mut cwd_depth = 0;
+    for (id, depth) in vfs.walk(Vfs::ROOT, None) {
+        let Some(children) = vfs.list(id) else {
+            continue;
+        };
+        if depth > 0 {
+            for _ in depth..=cwd_depth {
+                lines.push("$ cd ..".to_string());
+            }
+            lines.push(format!("$ cd {}", vfs.node(id).name));
+        }
+        cwd_depth = depth;
+        lines.push("$ ls".to_string());
+        for child in children {
+            let node = vfs.node(child);
+            lines.push(match node.kind {
+                NodeKind::File(size) => format!("{} {}", size, node.name),
+                NodeKind::Directory(_) => format!("dir {}", node.name),
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+/// The tree as JSON. Every node has a `name`, a `type` of `"file"` or
+/// `"directory"` and a total `size`; directories also have `children`.
+pub fn to_json(vfs: &Vfs) -> String {
+    let sizes = vfs.sizes();
+    let mut out = String::new();
+    let mut open = 0;
+    let mut previous = None;
+    for (id, depth) in vfs.walk(Vfs::ROOT, None) {
+        while open > depth {
+            out.push_str("]}");
+            open -= 1;
         }
+        if previous.is_some_and(|previous| previous >= depth) {
+            out.push(',');
+        }
+        let node = vfs.node(id);
+        let name = if node.parent.is_none() {
+            "/"
+        } else {
+            &node.name
+        };
+        match node.kind {
+            NodeKind::File(size) => out.push_str(&format!(
+                "{{\"name\":{},\"type\":\"file\",\"size\":{}}}",
+                json_string(name),
+                size
+            )),
+            NodeKind::Directory(_) => {
+                out.push_str(&format!(
+                    "{{\"name\":{},\"type\":\"directory\",\"size\":{},\"children\":[",
+                    json_string(name),
+                    sizes[id]
+                ));
+                open = depth + 1;
+            }
+        }
+        previous = Some(depth);
+    }
+    for _ in 0..open {
+        out.push_str("]}");
     }
+    out
 }
 
 #[aoc(day7, part1)]
-pub fn solve_part1(input: &[InputLine]) -> u32 {
-    let mut root = TreeNode::default();
-    parse_subtree(&mut input.iter().skip(1), &mut root);
-    let (_, ret) = dfs_1(&root);
-    ret
+pub fn solve_part1(input: &[InputLine]) -> Result<u64, TranscriptError> {
+    let vfs = Vfs::from_transcript(input)?;
+    Ok(small_directories_total(&vfs, &DiskPolicy::default()))
 }
 
 #[aoc(day7, part2)]
-pub fn solve_part2(input: &[InputLine]) -> u32 {
-    let mut root = TreeNode::default();
-    parse_subtree(&mut input.iter().skip(1), &mut root);
-    let (total_size, _) = dfs_1(&root);
-    let (_, ret) = dfs_2(70000000 - total_size, &root);
-    ret
+pub fn solve_part2(input: &[InputLine]) -> Result<u64, TranscriptError> {
+    let vfs = Vfs::from_transcript(input)?;
+    // The default policy never requires more than the whole disk.
+    Ok(smallest_cleanup(&vfs, &DiskPolicy::default()).unwrap())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::XorShift;
 
     static EXAMPLE: &str = "$ cd /
 $ ls
@@ -204,24 +801,517 @@ $ ls
             InputLine::LsOutput(LsOutput::File(7214296, "k".to_string())),
         ];
 
-        let actual = generate(EXAMPLE);
+        let actual = generate(EXAMPLE).unwrap();
 
         assert_eq!(expected, actual)
     }
 
     #[test]
     fn test2() {
-        let expected = 95437;
-        let actual = solve_part1(&generate(EXAMPLE));
+        let expected = Ok(95437);
+        let actual = solve_part1(&generate(EXAMPLE).unwrap());
 
         assert_eq!(expected, actual)
     }
 
     #[test]
     fn test3() {
-        let expected = 24933642;
-        let actual = solve_part2(&generate(EXAMPLE));
+        let expected = Ok(24933642);
+        let actual = solve_part2(&generate(EXAMPLE).unwrap());
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test4() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let e = vfs.lookup("/a/e").unwrap();
+
+        assert_eq!(14, vfs.node_count());
+        assert_eq!("/a/e", vfs.path(e));
+        assert!(vfs.is_dir(e));
+        assert_eq!(584, vfs.size(e));
+        assert_eq!(94853, vfs.size(vfs.lookup("/a").unwrap()));
+        assert_eq!(48381165, vfs.size(Vfs::ROOT));
+        assert_eq!(None, vfs.lookup("/a/x"));
+        assert_eq!(Some(Vfs::ROOT), vfs.lookup("/"));
+    }
+
+    #[test]
+    fn test5() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let names = |ids: Vec<NodeId>| {
+            ids.into_iter()
+                .map(|id| vfs.node(id).name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["a", "b.txt", "c.dat", "d"],
+            names(vfs.list(Vfs::ROOT).unwrap())
+        );
+        assert_eq!(None, vfs.list(vfs.lookup("/b.txt").unwrap()));
+        assert_eq!(
+            vec![(Vfs::ROOT, 0), (vfs.lookup("/a").unwrap(), 1)],
+            vfs.walk(Vfs::ROOT, Some(1))[..2]
+        );
+        assert_eq!(
+            vec![("/a/e/i".to_string(), 3)],
+            vfs.walk(Vfs::ROOT, None)
+                .into_iter()
+                .filter(|&(_, depth)| depth == 3)
+                .map(|(id, depth)| (vfs.path(id), depth))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test6() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let paths = |ids: Vec<NodeId>| ids.into_iter().map(|id| vfs.path(id)).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec!["/d/d.ext", "/d/d.log"],
+            paths(vfs.find(|node, _| node.name.starts_with("d.")))
+        );
+        assert_eq!(vec!["/d"], paths(vfs.find_by_name("d")));
+        assert_eq!(
+            vec!["/a", "/a/e"],
+            paths(
+                vfs.find(
+                    |node, size| matches!(node.kind, NodeKind::Directory(_)) && size <= 100_000
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test7() {
+        let mut vfs = Vfs::new();
+        let a = vfs.mkdir(Vfs::ROOT, "a").unwrap();
+        let f = vfs.add_file(a, "f", 10).unwrap();
+
+        assert_eq!(Ok(a), vfs.mkdir(Vfs::ROOT, "a"));
+        assert_eq!(Ok(f), vfs.add_file(a, "f", 10));
+        assert_eq!(
+            Err(VfsError::SizeMismatch {
+                path: "/a/f".to_string(),
+                old: 10,
+                new: 11
+            }),
+            vfs.add_file(a, "f", 11)
+        );
+        assert_eq!(
+            Err(VfsError::NotAFile("/a".to_string())),
+            vfs.add_file(Vfs::ROOT, "a", 1)
+        );
+        assert_eq!(
+            Err(VfsError::NotADirectory("/a/f".to_string())),
+            vfs.mkdir(a, "f")
+        );
+        assert_eq!(
+            Err(VfsError::NotADirectory("/a/f".to_string())),
+            vfs.mkdir(f, "g")
+        );
+
+        let empty = vfs.mkdir(a, "empty").unwrap();
+        assert!(vfs.is_dir(empty));
+        assert_eq!(Some(vec![]), vfs.list(empty));
+        assert_eq!(0, vfs.size(empty));
+    }
+
+    #[test]
+    fn test8() {
+        let input = generate(
+            "$ cd a
+$ cd b
+$ ls
+10 f
+$ cd /
+$ ls
+dir a
+5 g
+$ cd /a/b
+$ ls
+10 f
+dir c
+$ cd ../../a/./b/c
+$ ls
+1 h
+$ cd ..
+$ cd ..
+$ cd ..
+$ cd ..
+$ ls
+dir a
+5 g",
+        )
+        .unwrap();
+        let vfs = Vfs::from_transcript(&input).unwrap();
+
+        assert_eq!(16, vfs.size(Vfs::ROOT));
+        assert_eq!(11, vfs.size(vfs.lookup("/a/b").unwrap()));
+        assert_eq!(
+            Some(vfs.lookup("/a/b/c/h").unwrap()),
+            vfs.find_by_name("h").first().copied()
+        );
+        assert_eq!(7, vfs.node_count());
+    }
+
+    #[test]
+    fn test9() {
+        let input = generate("$ cd /\n$ ls\n10 f\n$ ls\n12 f").unwrap();
+        let expected = Err(TranscriptError {
+            line: 5,
+            error: VfsError::SizeMismatch {
+                path: "/f".to_string(),
+                old: 10,
+                new: 12,
+            },
+        });
+
+        assert_eq!(expected, Vfs::from_transcript(&input))
+    }
+
+    #[test]
+    fn test10() {
+        let input = generate("$ ls\ndir x\n$ ls\n3 x").unwrap();
+        let actual = Vfs::from_transcript(&input).unwrap_err();
+
+        assert_eq!(4, actual.line);
+        assert_eq!("line 4: /x is not a file", actual.to_string());
+
+        let input = generate("$ ls\n3 x\n$ cd x").unwrap();
+        let actual = Vfs::from_transcript(&input).unwrap_err();
+
+        assert_eq!(3, actual.line);
+        assert_eq!(VfsError::NotADirectory("/x".to_string()), actual.error);
+    }
+
+    #[test]
+    fn test11() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let policy = DiskPolicy {
+            capacity: 50_000_000,
+            required_free: 10_000_000,
+            small_threshold: 1_000,
+        };
+
+        assert_eq!(584, small_directories_total(&vfs, &policy));
+        assert_eq!(Some(24933642), smallest_cleanup(&vfs, &policy));
+
+        let policy = DiskPolicy {
+            required_free: 30_000_000,
+            ..policy
+        };
+        assert_eq!(Some(48381165), smallest_cleanup(&vfs, &policy));
+
+        let policy = DiskPolicy {
+            required_free: 60_000_000,
+            ..policy
+        };
+        assert_eq!(None, smallest_cleanup(&vfs, &policy));
+    }
+
+    #[test]
+    fn test12() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let expected = Some(Cleanup {
+            freed: 8504156,
+            paths: vec!["/c.dat".to_string()],
+            exact: true,
+        });
+
+        assert_eq!(expected, plan_cleanup(&vfs, &DiskPolicy::default()));
+
+        let policy = DiskPolicy {
+            required_free: 27_300_000,
+            ..DiskPolicy::default()
+        };
+        let expected = Some(Cleanup {
+            freed: 5626152 + 62596,
+            paths: vec!["/a/h.lst".to_string(), "/d/d.ext".to_string()],
+            exact: true,
+        });
+
+        assert_eq!(expected, plan_cleanup(&vfs, &policy));
+    }
+
+    #[test]
+    fn test13() {
+        let input = generate("$ ls\ndir a\n50 x\n$ cd a\n$ ls\n30 y\n15 z").unwrap();
+        let vfs = Vfs::from_transcript(&input).unwrap();
+        let policy = |required_free| DiskPolicy {
+            capacity: 100,
+            required_free,
+            small_threshold: 0,
+        };
+        let plan = |required_free| {
+            plan_cleanup(&vfs, &policy(required_free))
+                .map(|cleanup| (cleanup.freed, cleanup.paths.join(" ")))
+        };
+
+        assert_eq!(Some((0, "".to_string())), plan(5));
+        assert_eq!(Some((15, "/a/z".to_string())), plan(10));
+        assert_eq!(Some((45, "/a".to_string())), plan(45));
+        assert_eq!(Some((65, "/a/z /x".to_string())), plan(60));
+        assert_eq!(Some((95, "/".to_string())), plan(90));
+        assert_eq!(None, plan(110));
+    }
+
+    #[test]
+    fn test14() {
+        let mut vfs = Vfs::new();
+        let mut rng = XorShift::new(0x2545_f491_4f6c_dd1d);
+        for dir in 0..20 {
+            let parent = vfs.mkdir(Vfs::ROOT, &format!("d{}", dir)).unwrap();
+            for file in 0..50 {
+                let size = rng.next_u64() % 1_000_000 + 1;
+                vfs.add_file(parent, &format!("f{}", file), size).unwrap();
+            }
+        }
+        let total = vfs.size(Vfs::ROOT);
+        let policy = DiskPolicy {
+            capacity: total,
+            required_free: total / 3,
+            small_threshold: 0,
+        };
+        let cleanup = plan_cleanup(&vfs, &policy).unwrap();
+        let deleted = cleanup
+            .paths
+            .iter()
+            .map(|path| vfs.size(vfs.lookup(path).unwrap()))
+            .sum::<u64>();
+
+        assert!(!cleanup.exact);
+        assert_eq!(deleted, cleanup.freed);
+        assert!(cleanup.freed >= total / 3);
+        assert!(cleanup.freed <= total / 3 + total / 300);
+    }
+
+    #[test]
+    fn test15() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let expected = "[48381165] /
+├── [94853] a/
+│   ├── [584] e/
+│   │   └── [584] i
+│   ├── [29116] f
+│   ├── [2557] g
+│   └── [62596] h.lst
+├── [14848514] b.txt
+├── [8504156] c.dat
+└── [24933642] d/
+    ├── [5626152] d.ext
+    ├── [8033020] d.log
+    ├── [4060174] j
+    └── [7214296] k";
+
+        assert_eq!(expected, render_tree(&vfs));
+        assert_eq!("[0] /", render_tree(&Vfs::new()));
+    }
+
+    #[test]
+    fn test16() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let entries = |all| {
+            du(&vfs, all)
+                .into_iter()
+                .map(|(size, path)| format!("{} {}", size, path))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["48381165 /", "24933642 /d", "94853 /a", "584 /a/e"],
+            entries(false)
+        );
+        assert_eq!(
+            vec![
+                "48381165 /",
+                "24933642 /d",
+                "14848514 /b.txt",
+                "8504156 /c.dat"
+            ],
+            entries(true)[..4]
+        );
+        assert_eq!(vec!["584 /a/e", "584 /a/e/i"], entries(true)[12..]);
+    }
+
+    #[test]
+    fn test17() {
+        let vfs = Vfs::from_transcript(
+            &generate("$ ls\ndir a\n5 \"q\\\n$ cd a\n$ ls\ndir b\n3 c").unwrap(),
+        )
+        .unwrap();
+        let expected = concat!(
+            r#"{"name":"/","type":"directory","size":8,"children":["#,
+            r#"{"name":"\"q\\","type":"file","size":5},"#,
+            r#"{"name":"a","type":"directory","size":3,"children":["#,
+            r#"{"name":"b","type":"directory","size":0,"children":[]},"#,
+            r#"{"name":"c","type":"file","size":3}]}]}"#
+        );
+
+        assert_eq!(expected, to_json(&vfs));
+        assert_eq!(
+            r#"{"name":"/","type":"directory","size":0,"children":[]}"#,
+            to_json(&Vfs::new())
+        );
+    }
+
+    #[test]
+    fn test18() {
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        let transcript = to_transcript(&vfs);
+        let expected = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+5626152 d.ext
+8033020 d.log
+4060174 j
+7214296 k";
+
+        assert_eq!(expected, transcript);
+        assert_eq!(Ok(95437), solve_part1(&generate(&transcript).unwrap()));
+
+        let input = generate("$ cd a b\n$ ls\n12 c  d\ndir e f").unwrap();
+        let vfs = Vfs::from_transcript(&input).unwrap();
+        let actual = Vfs::from_transcript(&generate(&to_transcript(&vfs)).unwrap()).unwrap();
+
+        assert_eq!(vfs, actual);
+        assert_eq!(Some(12), vfs.lookup("/a b/c  d").map(|id| vfs.size(id)));
+        assert!(vfs.lookup("/a b/e f").is_some_and(|id| vfs.is_dir(id)));
+    }
+
+    #[test]
+    fn test19() {
+        let dir = std::env::temp_dir().join(format!("day7-test19-{}", std::process::id()));
+        let vfs = Vfs::from_transcript(&generate(EXAMPLE).unwrap()).unwrap();
+        vfs.write_to(&dir).unwrap();
+        let imported = Vfs::from_dir(&dir);
+        let length = fs::metadata(dir.join("d/d.log")).map(|m| m.len());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(8033020, length.unwrap());
+        assert_eq!(render_tree(&vfs), render_tree(&imported.unwrap()));
+    }
+
+    #[test]
+    fn test20() {
+        const DEPTH: u64 = 100_000;
+        let mut transcript = String::from("$ cd /");
+        for _ in 0..DEPTH {
+            transcript.push_str("\n$ ls\ndir d\n1 f\n$ cd d");
+        }
+        let vfs = Vfs::from_transcript(&generate(&transcript).unwrap()).unwrap();
+        let policy = DiskPolicy {
+            capacity: DEPTH + 1_000,
+            required_free: 13_345,
+            small_threshold: DEPTH,
+        };
+
+        assert_eq!(2 * DEPTH as usize + 1, vfs.node_count());
+        assert_eq!(DEPTH, vfs.size(Vfs::ROOT));
+        assert_eq!(
+            DEPTH * (DEPTH + 1) / 2,
+            small_directories_total(&vfs, &policy)
+        );
+        assert_eq!(Some(12_345), smallest_cleanup(&vfs, &policy));
+        assert_eq!(DEPTH as usize + 1, vfs.find(|_, size| size == 1).len());
+    }
+
+    #[test]
+    fn test21() {
+        let input = generate("$ ls\n10 ../escaped").unwrap();
+        let expected = Err(TranscriptError {
+            line: 2,
+            error: VfsError::InvalidName("../escaped".to_string()),
+        });
+
+        assert_eq!(expected, Vfs::from_transcript(&input));
+
+        let mut vfs = Vfs::new();
+        for name in ["", ".", "..", "a/b", "/"] {
+            assert_eq!(
+                Err(VfsError::InvalidName(name.to_string())),
+                vfs.mkdir(Vfs::ROOT, name)
+            );
+            assert_eq!(
+                Err(VfsError::InvalidName(name.to_string())),
+                vfs.add_file(Vfs::ROOT, name, 1)
+            );
+        }
+        assert_eq!(1, vfs.node_count());
+        assert_eq!(
+            "\"a/b\" is not a valid name",
+            VfsError::InvalidName("a/b".to_string()).to_string()
+        );
+    }
+
+    #[test]
+    fn test22() {
+        assert_eq!(
+            Err(ParseError::UnknownCommand {
+                line: 2,
+                command: "pwd".to_string()
+            }),
+            generate("$ ls\n$ pwd")
+        );
+        assert_eq!(
+            Err(ParseError::MissingDirectory { line: 1 }),
+            generate("$ cd")
+        );
+        assert_eq!(
+            Err(ParseError::MissingDirectory { line: 1 }),
+            generate("$ cd ")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidSize {
+                line: 2,
+                size: "abc".to_string()
+            }),
+            generate("$ ls\nabc f")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidEntry { line: 3 }),
+            generate("$ ls\ndir a\n12")
+        );
+        assert_eq!(
+            "line 2: unknown command \"pwd\"",
+            generate("$ ls\n$ pwd").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test23() {
+        let input = generate("$ ls\ndir empty\n0 zero\n50 x\n30 y").unwrap();
+        let vfs = Vfs::from_transcript(&input).unwrap();
+        let policy = DiskPolicy {
+            capacity: 100,
+            required_free: 40,
+            ..DiskPolicy::default()
+        };
+        let expected = Cleanup {
+            freed: 30,
+            paths: vec!["/y".to_string()],
+            exact: true,
+        };
+
+        assert_eq!(Some(expected), plan_cleanup(&vfs, &policy));
+    }
 }
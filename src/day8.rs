@@ -1,136 +1,71 @@
-use std::collections::HashSet;
-
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::grid::{Direction, Grid, GridError, Pos};
+
 #[aoc_generator(day8)]
-pub fn generate(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as u32).collect())
-        .collect()
+pub fn generate(input: &str) -> Result<Grid<u32>, GridError> {
+    Grid::parse_digits(input)
 }
 
-#[aoc(day8, part1)]
-pub fn solve_part1(input: &[Vec<u32>]) -> usize {
-    eprintln!("size is {}", input.len() * input[0].len());
-    let mut visible = HashSet::<(usize, usize)>::default();
-
-    let mut column_highs = input[0].iter().map(|x| *x).collect::<Vec<_>>();
-
-    // Top + Left
-
-    for (x, row) in input.iter().enumerate() {
-        let mut highest_left = row[0];
-        for (y, elem) in row.iter().enumerate() {
-            if x == 0 {
-                visible.insert((x, y));
-            }
-            if y == 0 {
-                visible.insert((x, y));
-            }
-            if *elem > highest_left {
-                highest_left = *elem;
-                visible.insert((x, y));
-            }
-            if *elem > column_highs[y] {
-                column_highs[y] = *elem;
-                visible.insert((x, y));
-            }
-        }
-    }
-
-    let mut column_highs = input.last().unwrap().iter().map(|x| *x).collect::<Vec<_>>();
-
-    // Bottom + Right
-
-    for (x, row) in input.iter().enumerate().rev() {
-        let mut highest_right = *row.last().unwrap();
-        for (y, elem) in row.iter().enumerate().rev() {
-            if x == row.len() - 1 {
-                visible.insert((x, y));
-            }
-            if y == input.len() - 1 {
-                visible.insert((x, y));
-            }
-            if *elem > highest_right {
-                highest_right = *elem;
-                visible.insert((x, y));
-            }
-            if *elem > column_highs[y] {
-                column_highs[y] = *elem;
-                visible.insert((x, y));
-            }
+/// Marks the trees along `line` that are taller than every tree before them.
+fn sweep<'a>(visible: &mut Grid<bool>, line: impl Iterator<Item = (Pos, &'a u32)>) {
+    let mut highest = None;
+    for (pos, &height) in line {
+        if highest < Some(height) {
+            highest = Some(height);
+            visible[pos] = true;
         }
     }
-
-    visible.len()
 }
 
-#[aoc(day8, part2)]
-pub fn solve_part2(input: &[Vec<u32>]) -> u32 {
-    let mut ret = 0;
-
-    for x in 0..input.len() {
-        for y in 0..input[0].len() {
-            let view = view_total(input, x, y);
-            if view > ret {
-                ret = view;
-            }
-        }
+#[aoc(day8, part1)]
+pub fn solve_part1(input: &Grid<u32>) -> usize {
+    let (width, height) = (input.width(), input.height());
+    let mut visible = Grid::new(width, height, false);
+    for y in 0..height {
+        let row = input.row(y);
+        sweep(&mut visible, (0..width).map(|x| (x, y)).zip(row));
+        sweep(
+            &mut visible,
+            (0..width).rev().map(|x| (x, y)).zip(row.iter().rev()),
+        );
     }
-
-    ret
-}
-
-fn view_total(input: &[Vec<u32>], x: usize, y: usize) -> u32 {
-    view_west(input, x, y)
-        * view_north(input, x, y)
-        * view_east(input, x, y)
-        * view_south(input, x, y)
-}
-
-fn view_west(input: &[Vec<u32>], x: usize, y: usize) -> u32 {
-    let mut ret = 0;
-    let elem = input[x][y];
-    for x_ in (0..x).rev() {
-        ret += 1;
-        if elem <= input[x_][y] {
-            break;
-        }
+    for x in 0..width {
+        sweep(
+            &mut visible,
+            (0..height).map(|y| (x, y)).zip(input.column(x)),
+        );
+        sweep(
+            &mut visible,
+            (0..height).rev().map(|y| (x, y)).zip(input.column(x).rev()),
+        );
     }
-    ret
+    visible.iter().filter(|&(_, &seen)| seen).count()
 }
 
-fn view_north(input: &[Vec<u32>], x: usize, y: usize) -> u32 {
-    let mut ret = 0;
-    let elem = input[x][y];
-    for y_ in (0..y).rev() {
-        ret += 1;
-        if elem <= input[x][y_] {
-            break;
-        }
-    }
-    ret
+#[aoc(day8, part2)]
+pub fn solve_part2(input: &Grid<u32>) -> u32 {
+    input
+        .positions()
+        .map(|pos| view_total(input, pos))
+        .max()
+        .unwrap_or(0)
 }
 
-fn view_east(input: &[Vec<u32>], x: usize, y: usize) -> u32 {
-    let mut ret = 0;
-    let elem = input[x][y];
-    for x_ in x + 1..input.len() {
-        ret += 1;
-        if elem <= input[x_][y] {
-            break;
-        }
-    }
-    ret
+fn view_total(input: &Grid<u32>, pos: Pos) -> u32 {
+    Direction::ORTHOGONAL
+        .into_iter()
+        .map(|direction| view(input, pos, direction))
+        .product()
 }
 
-fn view_south(input: &[Vec<u32>], x: usize, y: usize) -> u32 {
+/// How many trees can be seen from `pos` in `direction`, up to and including
+/// the first one that's at least as tall.
+fn view(input: &Grid<u32>, pos: Pos, direction: Direction) -> u32 {
     let mut ret = 0;
-    let elem = input[x][y];
-    for y_ in (y + 1)..input.len() {
+    for other in input.ray(pos, direction) {
         ret += 1;
-        if elem <= input[x][y_] {
+        if input[pos] <= input[other] {
             break;
         }
     }
@@ -149,13 +84,13 @@ mod tests {
 
     #[test]
     fn test1() {
-        let expected = vec![
+        let expected = Grid::from_rows(vec![
             vec![3, 0, 3, 7, 3],
             vec![2, 5, 5, 1, 2],
             vec![6, 5, 3, 3, 2],
             vec![3, 3, 5, 4, 9],
             vec![3, 5, 3, 9, 0],
-        ];
+        ]);
 
         let actual = generate(EXAMPLE);
 
@@ -165,7 +100,7 @@ mod tests {
     #[test]
     fn test2() {
         let expected = 21;
-        let actual = solve_part1(&generate(EXAMPLE));
+        let actual = solve_part1(&generate(EXAMPLE).unwrap());
 
         assert_eq!(expected, actual)
     }
@@ -173,7 +108,7 @@ mod tests {
     #[test]
     fn test3() {
         let expected = 4;
-        let actual = view_total(&generate(EXAMPLE), 1, 2);
+        let actual = view_total(&generate(EXAMPLE).unwrap(), (2, 1));
 
         assert_eq!(expected, actual)
     }
@@ -181,7 +116,7 @@ mod tests {
     #[test]
     fn test4() {
         let expected = 8;
-        let actual = view_total(&generate(EXAMPLE), 3, 2);
+        let actual = view_total(&generate(EXAMPLE).unwrap(), (2, 3));
 
         assert_eq!(expected, actual)
     }
@@ -189,8 +124,37 @@ mod tests {
     #[test]
     fn test5() {
         let expected = 8;
-        let actual = solve_part2(&generate(EXAMPLE));
+        let actual = solve_part2(&generate(EXAMPLE).unwrap());
 
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test6() {
+        let input = generate("3037325\n2551212\n6533265").unwrap();
+
+        assert_eq!((7, 3), (input.width(), input.height()));
+        assert_eq!(18, solve_part1(&input));
+        assert_eq!(4, solve_part2(&input));
+    }
+
+    #[test]
+    fn test7() {
+        let input = generate("303\n255\n653\n335\n353\n712\n490").unwrap();
+
+        assert_eq!((3, 7), (input.width(), input.height()));
+        assert_eq!(19, solve_part1(&input));
+        assert_eq!(4, solve_part2(&input));
+    }
+
+    #[test]
+    fn test8() {
+        let wide = generate("30373").unwrap();
+        let tall = generate("3\n0\n3\n7\n3").unwrap();
+
+        assert_eq!(5, solve_part1(&wide));
+        assert_eq!(5, solve_part1(&tall));
+        assert_eq!(0, solve_part2(&wide));
+        assert_eq!(0, solve_part2(&tall));
+    }
 }
@@ -57,7 +57,7 @@ pub fn solve_part1(input: &[Instruction]) -> usize {
 
     for instruction in input {
         for _ in 0..instruction.steps {
-            let prev = head.clone();
+            let prev = head;
             head = match instruction.direction {
                 Direction::Left => (head.0 - 1, head.1),
                 Direction::Up => (head.0, head.1 + 1),
@@ -99,7 +99,7 @@ fn reconcile(prev: &(i32, i32), head: &(i32, i32), tail: &(i32, i32)) -> (i32, i
     if head.1.abs_diff(tail.1) > 1 {
         return (tail.0, prev.1);
     }
-    tail.clone()
+    *tail
 }
 
 #[aoc(day9, part2)]
@@ -108,11 +108,11 @@ pub fn solve_part2(input: &[Instruction]) -> usize {
 
     visited.insert((0, 0));
 
-    let mut knots = vec![(0, 0); 10];
+    let mut knots = [(0, 0); 10];
 
     for instruction in input {
         for _ in 0..instruction.steps {
-            let mut prev = knots[0].clone();
+            let mut prev = knots[0];
             knots[0] = match instruction.direction {
                 Direction::Left => (prev.0 - 1, prev.1),
                 Direction::Up => (prev.0, prev.1 + 1),
@@ -0,0 +1,363 @@
+use std::{
+    error::Error,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+/// A cell position as `(x, y)`, where `x` is the column and `y` the row,
+/// counting from the top left.
+pub type Pos = (usize, usize);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ORTHOGONAL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+    pub const ALL: [Direction; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    /// The `(x, y)` step one cell this way.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Self::North => (0, -1),
+            Self::NorthEast => (1, -1),
+            Self::East => (1, 0),
+            Self::SouthEast => (1, 1),
+            Self::South => (0, 1),
+            Self::SouthWest => (-1, 1),
+            Self::West => (-1, 0),
+            Self::NorthWest => (-1, -1),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum GridError {
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidDigit {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells, expected {}",
+                row + 1,
+                found,
+                expected
+            ),
+            GridError::InvalidDigit { row, column, found } => write!(
+                f,
+                "row {}, column {}: {:?} is not a digit",
+                row + 1,
+                column + 1,
+                found
+            ),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+/// A rectangular grid, stored row by row in one `Vec`.
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T: From<u8>> Grid<T> {
+    /// Parses one digit per cell, one row per line.
+    pub fn parse_digits(input: &str) -> Result<Self, GridError> {
+        let rows = input
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(column, c)| match c.to_digit(10) {
+                        Some(digit) => Ok(T::from(digit as u8)),
+                        None => Err(GridError::InvalidDigit {
+                            row,
+                            column,
+                            found: c,
+                        }),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_rows(rows)
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (row, cells_in_row) in rows.into_iter().enumerate() {
+            if cells_in_row.len() != width {
+                return Err(GridError::Ragged {
+                    row,
+                    expected: width,
+                    found: cells_in_row.len(),
+                });
+            }
+            cells.extend(cells_in_row);
+        }
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if self.contains(pos) {
+            Some(&self.cells[pos.1 * self.width + pos.0])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.1 * self.width + pos.0])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(x < self.width, "column {} is out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` needs a non-zero size, and a grid without columns has no
+        // cells to chunk anyway.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// The neighbouring position in `direction`, if it's on the grid.
+    pub fn step(&self, (x, y): Pos, direction: Direction) -> Option<Pos> {
+        let (dx, dy) = direction.offset();
+        let next = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(next).then_some(next)
+    }
+
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::ORTHOGONAL
+            .into_iter()
+            .filter_map(move |direction| self.step(pos, direction))
+    }
+
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| self.step(pos, direction))
+    }
+
+    /// The positions from `pos` in `direction` up to the edge, not counting
+    /// `pos` itself.
+    pub fn ray(&self, pos: Pos, direction: Direction) -> impl Iterator<Item = Pos> + '_ {
+        let mut current = pos;
+        std::iter::from_fn(move || {
+            current = self.step(current, direction)?;
+            Some(current)
+        })
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        match self.get(pos) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside a {}x{} grid", pos, self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(pos) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside a {}x{} grid", pos, width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EXAMPLE: &str = "123
+456";
+
+    #[test]
+    fn test1() {
+        let grid = Grid::<u32>::parse_digits(EXAMPLE).unwrap();
+
+        assert_eq!(3, grid.width());
+        assert_eq!(2, grid.height());
+        assert_eq!(Some(&3), grid.get((2, 0)));
+        assert_eq!(4, grid[(0, 1)]);
+        assert_eq!(None, grid.get((3, 0)));
+        assert_eq!(None, grid.get((0, 2)));
+        assert_eq!(
+            vec![((0, 0), &1), ((1, 0), &2)],
+            grid.iter().take(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test2() {
+        let mut grid = Grid::<u8>::parse_digits(EXAMPLE).unwrap();
+        grid[(1, 1)] = 0;
+
+        assert_eq!(&[4, 0, 6], grid.row(1));
+        assert_eq!(vec![&2, &0], grid.column(1).collect::<Vec<_>>());
+        assert_eq!(vec![&6, &3], grid.column(2).rev().collect::<Vec<_>>());
+        assert_eq!(2, grid.rows().count());
+        assert_eq!(Grid::new(3, 2, true), grid.map(|&cell| cell < 7));
+    }
+
+    #[test]
+    fn test3() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(
+            vec![(1, 0), (0, 1)],
+            grid.neighbours4((0, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(4, grid.neighbours4((1, 1)).count());
+        assert_eq!(
+            vec![(1, 0), (1, 1), (0, 1)],
+            grid.neighbours8((0, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(8, grid.neighbours8((1, 1)).count());
+        assert_eq!(5, grid.neighbours8((2, 1)).count());
+    }
+
+    #[test]
+    fn test4() {
+        let grid = Grid::new(4, 2, 0);
+
+        assert_eq!(
+            vec![(2, 1), (3, 1)],
+            grid.ray((1, 1), Direction::East).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(0, 0)],
+            grid.ray((1, 1), Direction::NorthWest).collect::<Vec<_>>()
+        );
+        assert_eq!(0, grid.ray((1, 1), Direction::South).count());
+        assert_eq!(
+            vec![(2, 1), (1, 1), (0, 1)],
+            grid.ray((3, 1), Direction::West).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test5() {
+        assert_eq!(
+            Err(GridError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 2
+            }),
+            Grid::<u32>::parse_digits("123\n45")
+        );
+        assert_eq!(
+            "row 2, column 3: 'x' is not a digit",
+            Grid::<u32>::parse_digits("123\n45x")
+                .unwrap_err()
+                .to_string()
+        );
+
+        let empty = Grid::<u32>::parse_digits("").unwrap();
+        assert_eq!((0, 0), (empty.width(), empty.height()));
+        assert_eq!(0, empty.rows().count());
+        assert_eq!(0, empty.positions().count());
+    }
+}